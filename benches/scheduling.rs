@@ -0,0 +1,170 @@
+//! Benchmarks for finding the next bell under each scheduling mode, the
+//! quiet hours check and the config merge.
+//!
+//! Run with `cargo bench`. Times come from a `MockClock` fixed at a known
+//! start rather than the system clock, and are read before timing starts, so
+//! every run sees the same inputs.
+
+use chrono::{DateTime, Local, NaiveTime, TimeZone, Weekday};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mbell::clock::{Clock, MockClock};
+use mbell::config::{parse_minutes, Config, Profile, QuietHours};
+use mbell::schedule::{
+    effective_config, jitter_rng, next_bell_after, roll_jitter, PomodoroPhase, Scheduler,
+};
+use std::time::Duration;
+
+/// Wall-clock readings taken, one per bell
+const READINGS: usize = 144;
+
+fn time(hour: u32, min: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, min, 0).expect("valid time")
+}
+
+fn clock() -> MockClock {
+    let start = Local
+        .with_ymd_and_hms(2024, 1, 1, 0, 0, 0)
+        .earliest()
+        .expect("valid start time");
+    MockClock::new(start)
+}
+
+/// A day of readings from `clock`, one per bell at a 10 minute interval
+fn readings(clock: &MockClock) -> Vec<DateTime<Local>> {
+    (0..READINGS)
+        .map(|_| {
+            let now = clock.now_local();
+            clock.advance(Duration::from_secs(600));
+            now
+        })
+        .collect()
+}
+
+fn work_profile() -> Profile {
+    Profile {
+        interval: Some(15),
+        volume: Some(40),
+        gain_db: None,
+    }
+}
+
+/// The config behind each scheduling mode
+fn modes() -> Vec<(&'static str, Config)> {
+    let fixed = Config {
+        interval: 20,
+        ..Config::default()
+    };
+    let jitter = Config {
+        jitter_secs: 120,
+        jitter_seed: Some(7),
+        ..fixed.clone()
+    };
+    let random = Config {
+        jitter_secs: 120,
+        jitter_seed: None,
+        ..fixed.clone()
+    };
+    let mut overrides = fixed.clone();
+    overrides.profiles.insert("work".to_string(), work_profile());
+    let overrides =
+        effective_config(&overrides, Some("work"), Weekday::Mon).expect("known profile");
+    let quiet_hours = Config {
+        quiet_hours: Some(QuietHours {
+            start: time(22, 0),
+            end: time(7, 0),
+        }),
+        ..fixed.clone()
+    };
+
+    vec![
+        ("fixed", fixed),
+        ("jitter", jitter),
+        ("random", random),
+        ("override", overrides),
+        ("quiet_hours", quiet_hours),
+    ]
+}
+
+fn bench_next_bell_at(c: &mut Criterion) {
+    let mut group = c.benchmark_group("next_bell_at");
+    for (name, config) in modes() {
+        let clock = clock();
+        let scheduler = Scheduler::new(&config, clock.now_instant());
+        group.bench_function(name, |b| {
+            b.iter(|| scheduler.next_bell_at(black_box(&config)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_next_bell_after(c: &mut Criterion) {
+    let mut group = c.benchmark_group("next_bell_after");
+    for (name, config) in modes() {
+        let times = readings(&clock());
+        let mut rng = jitter_rng(&config);
+        let mut i = 0;
+        // A fresh jitter per bell, and whether the bell lands in quiet hours
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let now = times[i % READINGS];
+                i += 1;
+                let jitter = roll_jitter(&config, &mut rng);
+                let at = next_bell_after(black_box(now), &config, PomodoroPhase::Work, jitter);
+                config.in_quiet_hours(at.time())
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_quiet_hours(c: &mut Criterion) {
+    let same_day = QuietHours {
+        start: time(12, 0),
+        end: time(14, 0),
+    };
+    let overnight = QuietHours {
+        start: time(22, 0),
+        end: time(7, 0),
+    };
+
+    let mut group = c.benchmark_group("quiet_hours");
+    for (name, quiet) in [("same_day", same_day), ("overnight", overnight)] {
+        let times = readings(&clock());
+        let mut i = 0;
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                let now = times[i % READINGS];
+                i += 1;
+                quiet.contains(black_box(now.time()))
+            })
+        });
+    }
+    group.finish();
+}
+
+fn bench_config_merge(c: &mut Criterion) {
+    let mut config = Config::default();
+    config.profiles.insert("work".to_string(), work_profile());
+
+    c.bench_function("config_with_profile", |b| {
+        b.iter(|| config.with_profile(black_box("work")))
+    });
+}
+
+fn bench_parse_minutes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse_minutes");
+    for input in ["10", "20m", "1h30m", "5400s"] {
+        group.bench_function(input, |b| b.iter(|| parse_minutes(black_box(input))));
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_next_bell_at,
+    bench_next_bell_after,
+    bench_quiet_hours,
+    bench_config_merge,
+    bench_parse_minutes
+);
+criterion_main!(benches);
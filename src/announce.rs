@@ -0,0 +1,77 @@
+use crate::logging::LogLimiter;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+/// Longest an `announce_command` may run before it's killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Without a command, the bowl counts the session in cycles of this many
+/// bells: one strike for the first, two for the second, and so on
+pub const STRIKE_CYCLE: u64 = 5;
+
+/// Command failures, e.g. a missing `spd-say`, are reported at most every 10
+/// bells or once an hour while they persist
+static ANNOUNCE_FAILURES: LogLimiter = LogLimiter::new(10, Duration::from_secs(3600));
+
+/// Fill in `{count}` in an `announce_command` template
+pub fn render_command(template: &str, count: u64) -> String {
+    template.replace("{count}", &count.to_string())
+}
+
+/// Strikes of the bowl announcing session bell `count`
+pub fn strikes(count: u64) -> u8 {
+    (count.saturating_sub(1) % STRIKE_CYCLE + 1) as u8
+}
+
+/// Run `command` through `sh -c`, killing it and anything it started if it
+/// outlives `COMMAND_TIMEOUT`
+pub async fn run(command: &str) -> Result<(), String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+
+    match tokio::time::timeout(COMMAND_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) if status.success() => {
+            debug!("Announced with {:?}", command);
+            Ok(())
+        }
+        Ok(Ok(status)) => Err(format!("{:?} exited with {}", command, status)),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => {
+            // The child leads its own process group, so this also reaches
+            // whatever the shell started
+            if let Some(pid) = child.id() {
+                unsafe { libc::killpg(pid as libc::pid_t, libc::SIGKILL) };
+            }
+            Err(format!(
+                "{:?} still running after {}s, killed",
+                command,
+                COMMAND_TIMEOUT.as_secs()
+            ))
+        }
+    }
+}
+
+/// Run `command` in the background. Best-effort: failures are logged and
+/// never hold up the bell.
+pub fn run_async(command: String) {
+    tokio::spawn(async move {
+        match run(&command).await {
+            Ok(()) => ANNOUNCE_FAILURES.reset(),
+            Err(e) => {
+                if let Some(message) = ANNOUNCE_FAILURES.check(e) {
+                    warn!("Failed to announce bell count: {}", message);
+                }
+            }
+        }
+    });
+}
@@ -1,11 +1,64 @@
-use rodio::{Decoder, OutputStream, Sink};
-use std::io::Cursor;
+use crate::config::Config;
+use crate::logging::LogLimiter;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use rodio::source::Zero;
+use rodio::cpal::traits::HostTrait;
+use rodio::{Decoder, DeviceTrait, OutputStream, OutputStreamHandle, Sink, Source};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, Cursor};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant, SystemTime};
 use thiserror::Error;
-use tracing::{debug, error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
 
 // Embed the bowl sound at compile time
 const BOWL_SOUND: &[u8] = include_bytes!("../assets/bowl.ogg");
 
+/// Background playback failures, e.g. no audio device, are reported at most
+/// every 10 occurrences or once an hour while they persist
+static PLAYBACK_FAILURES: LogLimiter = LogLimiter::new(10, Duration::from_secs(3600));
+
+/// A custom sound that can't be read or decoded is reported at most every
+/// 10 bells or once an hour, falling back to the embedded bowl each time
+static SOUND_FAILURES: LogLimiter = LogLimiter::new(10, Duration::from_secs(3600));
+
+/// An `audio_device` that matches no output device is reported at most every
+/// 10 bells or once an hour, falling back to the default device each time
+static DEVICE_MISSES: LogLimiter = LogLimiter::new(10, Duration::from_secs(3600));
+
+/// How often a playing bell checks whether it has been cancelled
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Playback speed for the celebration chime, raising the bowl's pitch
+const CELEBRATION_SPEED: f32 = 1.5;
+
+/// How long the ambient sound is ducked for a bell of unknown length
+const DEFAULT_DUCK: Duration = Duration::from_secs(10);
+
+/// Mixed into `jitter_seed` for the volume variation's own sequence
+const VOLUME_SEED_SALT: u64 = 0x766f_6c75_6d65;
+
+/// How long past its expected end a bell may still be playing before the
+/// stream is taken to be dead, e.g. after the sound server restarted
+const STALL_MARGIN: Duration = Duration::from_secs(10);
+
+/// Volume of the pre-bell relative to the bell
+const PRE_BELL_VOLUME: f32 = 0.3;
+
+/// How long `AudioOutput::probe` waits for the audio thread to open a stream
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Allowed pre-gain range in decibels
+pub const MIN_GAIN_DB: f32 = -30.0;
+pub const MAX_GAIN_DB: f32 = 12.0;
+
 #[derive(Error, Debug)]
 pub enum AudioError {
     #[error("Failed to initialize audio output: {0}")]
@@ -16,80 +69,1060 @@ pub enum AudioError {
     PlaybackError(String),
 }
 
+/// An event with a sound of its own under `[sounds]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sound {
+    Bell,
+    Pause,
+    Resume,
+    SessionStart,
+}
+
+impl Sound {
+    /// The file configured for this event, or `None` for the embedded bowl
+    pub fn path(self, config: &Config) -> Option<&Path> {
+        let sounds = config.sounds.as_ref();
+        match self {
+            Sound::Bell => sounds
+                .and_then(|s| s.bell.as_deref())
+                .or(config.sound_path.as_deref()),
+            Sound::Pause => sounds?.pause.as_deref(),
+            Sound::Resume => sounds?.resume.as_deref(),
+            Sound::SessionStart => sounds?.session_start.as_deref(),
+        }
+    }
+
+    /// Whether the event makes a sound: the bell always, the others only
+    /// with a `[sounds]` table
+    pub fn enabled(self, config: &Config) -> bool {
+        self == Sound::Bell || config.sounds.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct AudioPlayer {
     volume: f32,
+    gain: f32,
+    speed: f32,
+    /// Strikes per bell and the silence between them
+    repeat: u8,
+    repeat_gap: Duration,
+    /// Fades at the start and end of each strike
+    fade_in: Duration,
+    fade_out: Duration,
+    /// Custom sound to play instead of the embedded bowl
+    sound_path: Option<PathBuf>,
+    /// Part of the name of the output device to use instead of the default
+    audio_device: Option<String>,
 }
 
 impl AudioPlayer {
     pub fn new(volume: u8) -> Self {
         Self {
             volume: volume as f32 / 100.0,
+            gain: 1.0,
+            speed: 1.0,
+            repeat: 1,
+            repeat_gap: Duration::ZERO,
+            fade_in: Duration::ZERO,
+            fade_out: Duration::ZERO,
+            sound_path: None,
+            audio_device: None,
+        }
+    }
+
+    /// Build a player from the configured sound, volume and pre-gain
+    pub fn from_config(config: &Config) -> Self {
+        Self::for_sound(config, Sound::Bell)
+    }
+
+    /// Build a player for `sound` with the configured volume and pre-gain.
+    /// Only the bell strikes `repeat` times.
+    pub fn for_sound(config: &Config, sound: Sound) -> Self {
+        let mut player = Self::new(config.volume);
+        player.set_gain_db(config.gain_db);
+        player.sound_path = sound.path(config).map(Path::to_path_buf);
+        player.audio_device = config.audio_device.clone();
+        if sound == Sound::Bell {
+            player.repeat = config.repeat;
         }
+        player.repeat_gap = Duration::from_secs_f32(config.repeat_gap_secs);
+        player.fade_in = Duration::from_millis(config.fade_in_ms);
+        player.fade_out = Duration::from_millis(config.fade_out_ms);
+        player
     }
 
     pub fn set_volume(&mut self, volume: u8) {
         self.volume = volume as f32 / 100.0;
     }
 
+    pub fn set_gain_db(&mut self, gain_db: f32) {
+        self.gain = gain_factor(gain_db);
+    }
+
+    pub fn set_repeat(&mut self, repeat: u8) {
+        self.repeat = repeat;
+    }
+
+    /// A brighter variant of this player, used to mark a new streak record
+    pub fn celebratory(&self) -> Self {
+        Self {
+            speed: CELEBRATION_SPEED,
+            ..self.clone()
+        }
+    }
+
+    /// A single soft strike of this player's sound, played shortly before
+    /// the bell
+    pub fn pre_bell(&self) -> Self {
+        Self {
+            volume: self.volume * PRE_BELL_VOLUME,
+            repeat: 1,
+            ..self.clone()
+        }
+    }
+
     pub fn play(&self) -> Result<(), AudioError> {
-        debug!("Playing bell sound at volume {:.0}%", self.volume * 100.0);
+        self.play_until(&CancellationToken::new())
+    }
 
-        // Get output stream - rodio auto-detects backend (PipeWire -> PulseAudio -> ALSA)
-        let (_stream, stream_handle) = OutputStream::try_default()
-            .map_err(|e| AudioError::OutputError(e.to_string()))?;
+    /// Volume 0 is silent mode: nothing is played and no device is opened
+    pub fn is_silent(&self) -> bool {
+        self.volume == 0.0
+    }
+
+    /// Play the bell, stopping early if `cancel` is triggered
+    pub fn play_until(&self, cancel: &CancellationToken) -> Result<(), AudioError> {
+        if self.is_silent() {
+            debug!("Volume is 0, skipping audio");
+            return Ok(());
+        }
+
+        let (_stream, stream_handle) = self.open_output()?;
 
         let sink = Sink::try_new(&stream_handle)
             .map_err(|e| AudioError::PlaybackError(e.to_string()))?;
+        self.play_on(&sink, cancel)
+    }
 
-        // Decode the embedded OGG file
-        let cursor = Cursor::new(BOWL_SOUND);
-        let source = Decoder::new(cursor)
-            .map_err(|e| AudioError::DecodeError(e.to_string()))?;
+    /// Queue the bell on `sink` and wait for it to finish or `cancel`
+    fn play_on(&self, sink: &Sink, cancel: &CancellationToken) -> Result<(), AudioError> {
+        self.queue(sink)?;
 
-        sink.set_volume(self.volume);
-        sink.append(source);
-        sink.sleep_until_end();
+        // Poll rather than sleep_until_end so playback can be cut short
+        while !sink.empty() {
+            if cancel.is_cancelled() {
+                sink.stop();
+                debug!("Bell playback cancelled");
+                return Ok(());
+            }
+            std::thread::sleep(CANCEL_POLL_INTERVAL);
+        }
 
         info!("Bell played successfully");
         Ok(())
     }
 
-    pub fn play_async(&self) {
-        let volume = self.volume;
-        tokio::task::spawn_blocking(move || {
-            if let Err(e) = play_with_volume(volume) {
-                error!("Failed to play bell: {}", e);
+    /// Append the bell's strikes to `sink` at this player's volume
+    fn queue(&self, sink: &Sink) -> Result<(), AudioError> {
+        debug!(
+            "Playing bell sound at volume {:.0}% with gain x{:.2}",
+            self.volume * 100.0,
+            self.gain
+        );
+
+        sink.set_volume(self.volume);
+        // Fading out needs the sound's length, so it is skipped if that is unknown
+        let clip = if self.fade_in.is_zero() && self.fade_out.is_zero() {
+            None
+        } else {
+            self.clip_duration()
+        };
+        let (fade_in, fade_out) = match clip {
+            Some(clip) => fit_fades(self.fade_in, self.fade_out, clip),
+            None => (self.fade_in, Duration::ZERO),
+        };
+        for strike in 0..self.repeat {
+            // Decoding consumes the source, so each strike gets a fresh one
+            let source = self.decoder()?;
+            if strike > 0 {
+                sink.append(
+                    Zero::<i16>::new(source.channels(), source.sample_rate())
+                        .take_duration(self.repeat_gap),
+                );
+            }
+            let source = FadeOut::new(source, fade_out, clip.unwrap_or_default()).fade_in(fade_in);
+            sink.append(source.amplify(self.gain).speed(self.speed));
+        }
+        Ok(())
+    }
+
+    /// How long a bell from this player lasts, strikes and gaps included
+    pub fn play_duration(&self) -> Option<Duration> {
+        let strikes = self.repeat as u32;
+        let sound = self.clip_duration()? * strikes + self.repeat_gap * strikes.saturating_sub(1);
+        Some(sound.div_f32(self.speed))
+    }
+
+    /// Length of the sound this player plays
+    fn clip_duration(&self) -> Option<Duration> {
+        self.sound_path
+            .as_deref()
+            .and_then(sound_file_duration)
+            .or_else(bowl_duration)
+    }
+
+    /// Open the configured output device, or the default one if none is set
+    /// or it can't be found
+    fn open_output(&self) -> Result<(OutputStream, OutputStreamHandle), AudioError> {
+        if let Some(wanted) = &self.audio_device {
+            match find_output_device(wanted) {
+                Some(device) => {
+                    DEVICE_MISSES.reset();
+                    return OutputStream::try_from_device(&device)
+                        .map_err(|e| AudioError::OutputError(e.to_string()));
+                }
+                None => {
+                    let available = output_device_names().unwrap_or_default().join(", ");
+                    let message = format!(
+                        "No audio device matching {:?}, using the default. Available: {}",
+                        wanted, available
+                    );
+                    if let Some(message) = DEVICE_MISSES.check(message) {
+                        warn!("{}", message);
+                    }
+                }
+            }
+        }
+
+        // rodio auto-detects the backend (PipeWire -> PulseAudio -> ALSA)
+        OutputStream::try_default().map_err(|e| AudioError::OutputError(e.to_string()))
+    }
+
+    /// Decode the custom sound if one is set and usable, otherwise the
+    /// embedded bowl
+    fn decoder(&self) -> Result<Decoder<Cursor<Cow<'static, [u8]>>>, AudioError> {
+        if let Some(path) = &self.sound_path {
+            match load_sound(path) {
+                Ok(decoder) => {
+                    SOUND_FAILURES.reset();
+                    return Ok(decoder);
+                }
+                Err(e) => {
+                    if let Some(message) = SOUND_FAILURES.check(e.to_string()) {
+                        warn!("{}, using the embedded bowl instead", message);
+                    }
+                }
+            }
+        }
+
+        Decoder::new(Cursor::new(Cow::Borrowed(BOWL_SOUND)))
+            .map_err(|e| AudioError::DecodeError(e.to_string()))
+    }
+
+    /// Play in the background, returning a token that stops the playback
+    pub fn play_async(&self) -> CancellationToken {
+        let cancel = CancellationToken::new();
+        if self.is_silent() {
+            debug!("Volume is 0, skipping audio");
+            return cancel;
+        }
+
+        let player = self.clone();
+        let token = cancel.clone();
+        tokio::task::spawn_blocking(move || match player.play_until(&token) {
+            Ok(()) => PLAYBACK_FAILURES.reset(),
+            Err(e) => {
+                if let Some(message) = PLAYBACK_FAILURES.check(e.to_string()) {
+                    error!("Failed to play bell: {}", message);
+                }
             }
         });
+        cancel
     }
 }
 
-fn play_with_volume(volume: f32) -> Result<(), AudioError> {
-    let (_stream, stream_handle) = OutputStream::try_default()
+/// Lowers the volume to silence over the last `fade` of a source that is
+/// `length` long
+struct FadeOut<S> {
+    inner: S,
+    /// Samples left in the source, across all channels
+    remaining: u64,
+    /// Samples the fade spans
+    fade_samples: u64,
+}
+
+impl<S: Source> FadeOut<S>
+where
+    S::Item: rodio::Sample,
+{
+    fn new(inner: S, fade: Duration, length: Duration) -> Self {
+        let samples_per_sec = inner.sample_rate() as f64 * inner.channels() as f64;
+        let samples = |d: Duration| (d.as_secs_f64() * samples_per_sec) as u64;
+        let remaining = samples(length);
+        Self {
+            fade_samples: samples(fade).min(remaining),
+            remaining,
+            inner,
+        }
+    }
+}
+
+impl<S: Source> Iterator for FadeOut<S>
+where
+    S::Item: rodio::Sample,
+{
+    type Item = S::Item;
+
+    fn next(&mut self) -> Option<S::Item> {
+        let sample = self.inner.next()?;
+        self.remaining = self.remaining.saturating_sub(1);
+        if self.remaining >= self.fade_samples {
+            return Some(sample);
+        }
+        let factor = self.remaining as f32 / self.fade_samples as f32;
+        Some(rodio::Sample::amplify(sample, factor))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<S: Source> Source for FadeOut<S>
+where
+    S::Item: rodio::Sample,
+{
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+/// Names of the output devices on the default audio host
+pub fn output_device_names() -> Result<Vec<String>, AudioError> {
+    let devices = rodio::cpal::default_host()
+        .output_devices()
         .map_err(|e| AudioError::OutputError(e.to_string()))?;
+    Ok(devices.filter_map(|device| device.name().ok()).collect())
+}
+
+/// Whether an output device name contains `wanted`, as for `audio_device`
+pub fn output_device_exists(wanted: &str) -> bool {
+    find_output_device(wanted).is_some()
+}
+
+/// Open an output stream for `config`'s device the way a bell would, to
+/// check there is anywhere to play it
+pub fn check_output(config: &Config) -> Result<(), AudioError> {
+    AudioPlayer::from_config(config).open_output().map(|_| ())
+}
+
+/// First output device whose name contains `wanted`, ignoring case
+fn find_output_device(wanted: &str) -> Option<rodio::Device> {
+    let wanted = wanted.to_lowercase();
+    rodio::cpal::default_host()
+        .output_devices()
+        .ok()?
+        .find(|device| {
+            device
+                .name()
+                .is_ok_and(|name| name.to_lowercase().contains(&wanted))
+        })
+}
+
+/// A format a custom sound file may be in
+#[derive(Debug, Clone, Copy)]
+pub struct SoundFormat {
+    pub name: &'static str,
+    pub extensions: &'static [&'static str],
+    /// Cargo features that add a decoder for it, if it isn't always built in
+    pub features: &'static str,
+    /// Whether this build can decode it
+    pub available: bool,
+}
+
+/// Every format rodio can decode, and whether this build includes it
+pub const SOUND_FORMATS: &[SoundFormat] = &[
+    SoundFormat {
+        name: "OGG Vorbis",
+        extensions: &["ogg", "oga"],
+        features: "",
+        available: true,
+    },
+    SoundFormat {
+        name: "FLAC",
+        extensions: &["flac"],
+        features: "flac or symphonia",
+        available: cfg!(any(feature = "flac", feature = "symphonia")),
+    },
+    SoundFormat {
+        name: "WAV",
+        extensions: &["wav"],
+        features: "wav",
+        available: cfg!(feature = "wav"),
+    },
+    SoundFormat {
+        name: "MP3",
+        extensions: &["mp3"],
+        features: "mp3 or symphonia",
+        available: cfg!(any(feature = "mp3", feature = "symphonia")),
+    },
+];
 
-    let sink = Sink::try_new(&stream_handle)
-        .map_err(|e| AudioError::PlaybackError(e.to_string()))?;
+/// The format `path`'s extension names, if this build can't decode it
+fn missing_format(path: &Path) -> Option<&'static SoundFormat> {
+    let ext = path.extension()?.to_str()?;
+    SOUND_FORMATS.iter().find(|format| {
+        !format.available && format.extensions.iter().any(|e| e.eq_ignore_ascii_case(ext))
+    })
+}
+
+/// Read and decode a sound file. Supports OGG Vorbis, plus FLAC, WAV and
+/// MP3 when built with the matching feature.
+fn load_sound(path: &Path) -> Result<Decoder<Cursor<Cow<'static, [u8]>>>, AudioError> {
+    let data = std::fs::read(path)
+        .map_err(|e| AudioError::DecodeError(format!("Failed to read {}: {}", path.display(), e)))?;
+    Decoder::new(Cursor::new(Cow::Owned(data))).map_err(|e| match missing_format(path) {
+        Some(format) => AudioError::DecodeError(format!(
+            "Can't play {}: {} support isn't built in (cargo feature {})",
+            path.display(),
+            format.name,
+            format.features
+        )),
+        None => AudioError::DecodeError(format!("Failed to decode {}: {}", path.display(), e)),
+    })
+}
+
+/// Check that `path` decodes as a supported format
+pub fn check_sound(path: &Path) -> Result<(), AudioError> {
+    load_sound(path).map(drop)
+}
+
+/// The custom sound file that will actually be played, or `None` when the
+/// embedded bowl is used because none is set or it can't be decoded
+pub fn resolved_sound(config: &Config) -> Option<&Path> {
+    let path = Sound::Bell.path(config)?;
+    load_sound(path).is_ok().then_some(path)
+}
 
-    let cursor = Cursor::new(BOWL_SOUND);
-    let source = Decoder::new(cursor)
-        .map_err(|e| AudioError::DecodeError(e.to_string()))?;
+/// Length of a sound file, or `None` if it can't be read or decoded.
+/// Measuring may decode the whole file, so the result is cached until the
+/// file's modification time changes.
+pub fn sound_file_duration(path: &Path) -> Option<Duration> {
+    static DURATIONS: Mutex<BTreeMap<PathBuf, (SystemTime, Option<Duration>)>> =
+        Mutex::new(BTreeMap::new());
 
+    let modified = std::fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+    if let Some(&(at, duration)) = DURATIONS.lock().unwrap_or_else(|e| e.into_inner()).get(path) {
+        if at == modified {
+            return duration;
+        }
+    }
+    let duration = load_sound(path).ok().and_then(measure);
+    DURATIONS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(path.to_path_buf(), (modified, duration));
+    duration
+}
+
+/// Decode a sound and measure its length. Uses the duration reported by the
+/// decoder when available and falls back to counting samples; returns `None`
+/// if the data can't be decoded or is empty.
+pub fn duration_of(data: &'static [u8]) -> Option<Duration> {
+    measure(Decoder::new(Cursor::new(data)).ok()?)
+}
+
+fn measure<S: Source>(source: S) -> Option<Duration>
+where
+    S::Item: rodio::Sample,
+{
+    if let Some(duration) = source.total_duration() {
+        return Some(duration);
+    }
+
+    let channels = source.channels() as u64;
+    let sample_rate = source.sample_rate() as u64;
+    if channels == 0 || sample_rate == 0 {
+        return None;
+    }
+
+    let frames = source.count() as u64 / channels;
+    (frames > 0).then(|| Duration::from_secs_f64(frames as f64 / sample_rate as f64))
+}
+
+/// Length of the embedded bowl sound, decoded once and cached
+pub fn bowl_duration() -> Option<Duration> {
+    static DURATION: OnceLock<Option<Duration>> = OnceLock::new();
+    *DURATION.get_or_init(|| duration_of(BOWL_SOUND))
+}
+
+/// Convert a pre-gain in decibels to a linear amplification factor,
+/// clamped to a range that keeps typical recordings clear of distortion
+pub fn gain_factor(gain_db: f32) -> f32 {
+    10f32.powf(gain_db.clamp(MIN_GAIN_DB, MAX_GAIN_DB) / 20.0)
+}
+
+/// Warn about pre-gain settings that are out of range or likely to clip
+pub fn check_gain(volume: u8, gain_db: f32) {
+    if !(MIN_GAIN_DB..=MAX_GAIN_DB).contains(&gain_db) {
+        warn!(
+            "gain_db {} is outside {}..={}, clamping",
+            gain_db, MIN_GAIN_DB, MAX_GAIN_DB
+        );
+    }
+
+    let peak = volume as f32 / 100.0 * gain_factor(gain_db);
+    if peak > 1.0 {
+        warn!(
+            "Volume {}% with {:+.1} dB gain amplifies past full scale (x{:.2}), the bell may clip",
+            volume, gain_db, peak
+        );
+    }
+}
+
+/// Shorten fades that together run longer than `clip`, keeping their ratio
+pub fn fit_fades(fade_in: Duration, fade_out: Duration, clip: Duration) -> (Duration, Duration) {
+    let total = fade_in + fade_out;
+    if total <= clip {
+        return (fade_in, fade_out);
+    }
+    let scale = clip.as_secs_f64() / total.as_secs_f64();
+    (fade_in.mul_f64(scale), fade_out.mul_f64(scale))
+}
+
+/// Warn about fades that don't fit in the bell sound and will be shortened
+pub fn check_fades(config: &Config) {
+    let total = Duration::from_millis(config.fade_in_ms + config.fade_out_ms);
+    if total.is_zero() {
+        return;
+    }
+
+    let clip = resolved_sound(config)
+        .and_then(sound_file_duration)
+        .or_else(bowl_duration);
+    if let Some(clip) = clip.filter(|&clip| total > clip) {
+        warn!(
+            "fade_in_ms + fade_out_ms ({}ms) is longer than the {:.1}s bell sound, shortening both to fit",
+            total.as_millis(),
+            clip.as_secs_f32()
+        );
+    }
+}
+
+/// The daemon's audio: one output stream kept open for its whole lifetime,
+/// so bells start without the delay (and occasional click) of opening a
+/// device each time, plus the looping ambient sound. The stream isn't
+/// `Send`, so it lives on a dedicated thread driven over a channel; the
+/// thread ends when the last handle is dropped.
+#[derive(Clone)]
+pub struct AudioOutput {
+    commands: mpsc::Sender<OutputCommand>,
+    /// Whether the last `probe` found an output; sounds are skipped if not
+    available: Arc<AtomicBool>,
+}
+
+enum OutputCommand {
+    /// Play a bell, ducking the ambient sound while it strikes
+    Bell {
+        player: AudioPlayer,
+        cancel: CancellationToken,
+    },
+    /// Loop `path` at `volume`, replacing any ambient sound already playing
+    Ambient {
+        player: AudioPlayer,
+        path: PathBuf,
+        volume: f32,
+    },
+    StopAmbient,
+    /// Open the stream for `player`'s device if it isn't already, reporting
+    /// whether that worked
+    Probe {
+        player: AudioPlayer,
+        reply: mpsc::Sender<Result<(), String>>,
+    },
+}
+
+impl AudioOutput {
+    pub fn new() -> Self {
+        let (commands, rx) = mpsc::channel();
+        std::thread::Builder::new()
+            .name("mbell-audio".to_string())
+            .spawn(move || OutputThread::default().run(rx))
+            .expect("failed to spawn audio thread");
+        Self {
+            commands,
+            available: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Check whether an output stream can be opened for `config`'s device,
+    /// so a machine without audio gets one warning instead of an error per
+    /// bell. Blocks for up to `PROBE_TIMEOUT`.
+    pub fn probe(&self, config: &Config) -> bool {
+        let (reply, result) = mpsc::channel();
+        let _ = self.commands.send(OutputCommand::Probe {
+            player: AudioPlayer::from_config(config),
+            reply,
+        });
+        let available = match result.recv_timeout(PROBE_TIMEOUT) {
+            Ok(Ok(())) => true,
+            Ok(Err(e)) => {
+                if self.is_available() {
+                    warn!(
+                        "No audio output ({}), bells will be silent until a reload finds one",
+                        e
+                    );
+                }
+                false
+            }
+            Err(_) => {
+                if self.is_available() {
+                    warn!("Audio output didn't respond, bells will be silent until a reload");
+                }
+                false
+            }
+        };
+        if available && !self.is_available() {
+            info!("Audio output available again");
+        }
+        self.available.store(available, Ordering::Relaxed);
+        available
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.available.load(Ordering::Relaxed)
+    }
+
+    /// Play `player`'s bell in the background, returning a token that stops it
+    pub fn play(&self, player: &AudioPlayer) -> CancellationToken {
+        let cancel = CancellationToken::new();
+        if player.is_silent() {
+            debug!("Volume is 0, skipping audio");
+            return cancel;
+        }
+        if !self.is_available() {
+            debug!("No audio output, skipping audio");
+            return cancel;
+        }
+        let _ = self.commands.send(OutputCommand::Bell {
+            player: player.clone(),
+            cancel: cancel.clone(),
+        });
+        cancel
+    }
+
+    /// Start, restart or stop the ambient sound to match `ambient_path` and
+    /// `ambient_volume`
+    pub fn set_ambient(&self, config: &Config) {
+        let command = match &config.ambient_path {
+            Some(path) if config.ambient_volume > 0 && self.is_available() => {
+                OutputCommand::Ambient {
+                    player: AudioPlayer::from_config(config),
+                    path: path.clone(),
+                    volume: config.ambient_volume as f32 / 100.0,
+                }
+            }
+            _ => OutputCommand::StopAmbient,
+        };
+        let _ = self.commands.send(command);
+    }
+}
+
+impl Default for AudioOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// An output stream and the `audio_device` it was opened for
+struct OpenStream {
+    device: Option<String>,
+    _stream: OutputStream,
+    handle: OutputStreamHandle,
+}
+
+/// A bell playing on the shared stream
+struct PlayingBell {
+    sink: Sink,
+    cancel: CancellationToken,
+    /// When the bell should have finished by, if its length is known
+    stalled_after: Option<Instant>,
+}
+
+/// The looping ambient sound, playing on the shared stream
+struct Ambient {
+    sink: Sink,
+    path: PathBuf,
+    volume: f32,
+}
+
+#[derive(Default)]
+struct OutputThread {
+    stream: Option<OpenStream>,
+    bells: Vec<PlayingBell>,
+    ambient: Option<Ambient>,
+    ducked_until: Option<Instant>,
+}
+
+impl OutputThread {
+    fn run(mut self, commands: mpsc::Receiver<OutputCommand>) {
+        loop {
+            // Wake to check on playing bells and to end a duck on time
+            let poll = (!self.bells.is_empty()).then(|| Instant::now() + CANCEL_POLL_INTERVAL);
+            let wake = match (poll, self.ducked_until) {
+                (Some(poll), Some(duck)) => Some(poll.min(duck)),
+                (poll, duck) => poll.or(duck),
+            };
+            let command = match wake {
+                Some(wake) => {
+                    match commands.recv_timeout(wake.saturating_duration_since(Instant::now())) {
+                        Ok(command) => Some(command),
+                        Err(RecvTimeoutError::Timeout) => None,
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+                None => match commands.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => break,
+                },
+            };
+
+            match command {
+                Some(OutputCommand::Bell { player, cancel }) => match self.start_bell(&player) {
+                    Ok(sink) => {
+                        PLAYBACK_FAILURES.reset();
+                        let stalled_after = player
+                            .play_duration()
+                            .map(|length| Instant::now() + length + STALL_MARGIN);
+                        self.bells.push(PlayingBell {
+                            sink,
+                            cancel,
+                            stalled_after,
+                        });
+                        self.duck_for(&player);
+                    }
+                    Err(e) => {
+                        if let Some(message) = PLAYBACK_FAILURES.check(e.to_string()) {
+                            error!("Failed to play bell: {}", message);
+                        }
+                    }
+                },
+                Some(OutputCommand::Ambient {
+                    player,
+                    path,
+                    volume,
+                }) => {
+                    // Stop the old loop first so the two never overlap
+                    self.ambient = None;
+                    self.ducked_until = None;
+                    match self.start_ambient(&player, &path, volume) {
+                        Ok(ambient) => {
+                            info!("Playing ambient sound {:?}", path);
+                            self.ambient = Some(ambient);
+                        }
+                        Err(e) => warn!("Failed to play ambient sound: {}", e),
+                    }
+                }
+                Some(OutputCommand::StopAmbient) => {
+                    let stopped = self.ambient.take();
+                    if stopped.is_some() {
+                        info!("Ambient sound stopped");
+                    }
+                }
+                Some(OutputCommand::Probe { player, reply }) => {
+                    // Open afresh, so a device that came back is found even
+                    // while a dead stream is cached
+                    self.close_output();
+                    let result = self.output(&player).map(|_| ()).map_err(|e| e.to_string());
+                    let _ = reply.send(result);
+                }
+                None => {}
+            }
+
+            self.reap_bells();
+            if self
+                .ducked_until
+                .is_some_and(|until| until <= Instant::now())
+            {
+                self.ducked_until = None;
+                if let Some(ambient) = &self.ambient {
+                    ambient.sink.set_volume(ambient.volume);
+                }
+            }
+        }
+        debug!("Audio thread stopped");
+    }
+
+    /// The shared stream, opened on first use and reopened if `player` wants
+    /// another device. The ambient sound moves to a newly opened stream.
+    fn output(&mut self, player: &AudioPlayer) -> Result<&OutputStreamHandle, AudioError> {
+        if self
+            .stream
+            .as_ref()
+            .is_none_or(|open| open.device != player.audio_device)
+        {
+            // Anything still playing on the old stream stops with it
+            self.close_output();
+            let (stream, handle) = player.open_output()?;
+            debug!("Opened audio output stream");
+            if let Some(ambient) = self.ambient.take() {
+                match ambient_sink(&handle, &ambient.path, ambient.volume) {
+                    Ok(sink) => self.ambient = Some(Ambient { sink, ..ambient }),
+                    Err(e) => warn!("Failed to restart ambient sound: {}", e),
+                }
+            }
+            self.stream = Some(OpenStream {
+                device: player.audio_device.clone(),
+                _stream: stream,
+                handle,
+            });
+        }
+        Ok(&self.stream.as_ref().expect("stream was just opened").handle)
+    }
+
+    /// Drop the shared stream, so the next sound opens a new one
+    fn close_output(&mut self) {
+        if self.stream.take().is_some() {
+            self.bells.clear();
+            debug!("Closed audio output stream");
+        }
+    }
+
+    /// Create a sink on the shared stream with `start`, reopening the stream
+    /// and trying once more if playback fails, as it does on a stream whose
+    /// device went away. A sound that can't be decoded isn't retried.
+    fn on_output<T>(
+        &mut self,
+        player: &AudioPlayer,
+        start: impl Fn(&OutputStreamHandle) -> Result<T, AudioError>,
+    ) -> Result<T, AudioError> {
+        match start(self.output(player)?) {
+            Err(e @ AudioError::PlaybackError(_)) => {
+                debug!("Playback failed ({}), reopening the audio output", e);
+                self.close_output();
+                start(self.output(player)?)
+            }
+            started => started,
+        }
+    }
+
+    fn start_bell(&mut self, player: &AudioPlayer) -> Result<Sink, AudioError> {
+        self.on_output(player, |handle| {
+            let sink =
+                Sink::try_new(handle).map_err(|e| AudioError::PlaybackError(e.to_string()))?;
+            player.queue(&sink)?;
+            Ok(sink)
+        })
+    }
+
+    /// Loop the sound at `path` without gaps on its own sink
+    fn start_ambient(
+        &mut self,
+        player: &AudioPlayer,
+        path: &Path,
+        volume: f32,
+    ) -> Result<Ambient, AudioError> {
+        let sink = self.on_output(player, |handle| ambient_sink(handle, path, volume))?;
+        Ok(Ambient {
+            sink,
+            path: path.to_path_buf(),
+            volume,
+        })
+    }
+
+    /// Silence the ambient sound while `player`'s bell strikes
+    fn duck_for(&mut self, player: &AudioPlayer) {
+        if let Some(ambient) = &self.ambient {
+            ambient.sink.set_volume(0.0);
+            let until = Instant::now() + player.play_duration().unwrap_or(DEFAULT_DUCK);
+            self.ducked_until = Some(self.ducked_until.map_or(until, |current| current.max(until)));
+        }
+    }
+
+    /// Stop cancelled bells and forget finished ones. A bell still playing
+    /// long after it should have ended means the stream has died, so it is
+    /// closed for the next sound to reopen.
+    fn reap_bells(&mut self) {
+        let now = Instant::now();
+        let mut stalled = false;
+        self.bells.retain(|bell| {
+            if bell.cancel.is_cancelled() {
+                bell.sink.stop();
+                debug!("Bell playback cancelled");
+                false
+            } else if bell.sink.empty() {
+                info!("Bell played successfully");
+                false
+            } else if bell.stalled_after.is_some_and(|after| after <= now) {
+                stalled = true;
+                false
+            } else {
+                true
+            }
+        });
+        if stalled {
+            warn!("Bell never finished playing, reopening the audio output");
+            self.close_output();
+        }
+    }
+}
+
+/// A sink on `handle` looping the sound at `path` without gaps
+fn ambient_sink(handle: &OutputStreamHandle, path: &Path, volume: f32) -> Result<Sink, AudioError> {
+    let file = File::open(path).map_err(|e| {
+        AudioError::DecodeError(format!("Failed to read {}: {}", path.display(), e))
+    })?;
+    let source = Decoder::new_looped(BufReader::new(file)).map_err(|e| {
+        AudioError::DecodeError(format!("Failed to decode {}: {}", path.display(), e))
+    })?;
+    let sink = Sink::try_new(handle).map_err(|e| AudioError::PlaybackError(e.to_string()))?;
     sink.set_volume(volume);
     sink.append(source);
-    sink.sleep_until_end();
+    Ok(sink)
+}
+
+/// `volume` moved by a random amount of up to `variation` percent of it
+/// either way
+pub fn vary_volume(volume: u8, variation: u8, rng: &mut impl Rng) -> u8 {
+    let spread = (volume as u32 * variation as u32 / 100) as i32;
+    if spread == 0 {
+        return volume;
+    }
+    (volume as i32 + rng.random_range(-spread..=spread)).clamp(0, 100) as u8
+}
+
+/// The volume variation source: seeded from `jitter_seed` when set, but
+/// apart from the jitter so the two sequences don't move together
+pub fn volume_rng(config: &Config) -> StdRng {
+    match config.jitter_seed {
+        Some(seed) => StdRng::seed_from_u64(seed ^ VOLUME_SEED_SALT),
+        None => StdRng::from_os_rng(),
+    }
+}
 
-    Ok(())
+/// The configured volume with one bell's `volume_variation` applied, from
+/// one sequence per process so successive bells differ
+fn varied_volume(config: &Config) -> u8 {
+    static RNG: OnceLock<Mutex<StdRng>> = OnceLock::new();
+    let mut rng = RNG
+        .get_or_init(|| Mutex::new(volume_rng(config)))
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    vary_volume(config.volume, config.volume_variation, &mut *rng)
 }
 
-/// Ring the bell once (convenience function)
-pub fn ring(volume: u8) -> Result<(), AudioError> {
-    let player = AudioPlayer::new(volume);
-    player.play()
+/// Ring the bell once with the configured sound settings, as the daemon
+/// would, at `volume` instead of the configured, varied volume if given
+pub fn ring(config: &Config, volume: Option<u8>) -> Result<(), AudioError> {
+    ring_player(config, volume).play()
 }
 
-/// Ring the bell asynchronously (non-blocking)
-pub fn ring_async(volume: u8) {
-    let player = AudioPlayer::new(volume);
+/// The player `ring` uses, at `volume` if given
+pub fn ring_player(config: &Config, volume: Option<u8>) -> AudioPlayer {
+    let mut player = AudioPlayer::from_config(config);
+    player.set_volume(volume.unwrap_or_else(|| varied_volume(config)));
+    player
+}
+
+/// Play `sound` asynchronously (non-blocking), at `volume` if given. Without
+/// one, bells get `volume_variation` like `ring`.
+pub fn ring_async(config: &Config, sound: Sound, volume: Option<u8>) {
+    let mut player = AudioPlayer::for_sound(config, sound);
+    match volume {
+        Some(volume) => player.set_volume(volume),
+        None if sound == Sound::Bell => player.set_volume(varied_volume(config)),
+        None => {}
+    }
     player.play_async();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_cuts_playback_short() {
+        let mut player = AudioPlayer::new(50);
+        player.set_repeat(10);
+        let full = player.play_duration().unwrap();
+
+        // Nothing reads from an idle sink, so only the cancel can end playback
+        let (sink, _output) = Sink::new_idle();
+        let cancel = CancellationToken::new();
+        let canceller = {
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(100));
+                cancel.cancel();
+            })
+        };
+
+        let started = Instant::now();
+        player.play_on(&sink, &cancel).unwrap();
+        let elapsed = started.elapsed();
+        canceller.join().unwrap();
+
+        assert!(
+            elapsed < full / 4,
+            "took {:?} of a {:?} bell",
+            elapsed,
+            full
+        );
+    }
+
+    #[test]
+    fn sound_duration_follows_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bowl.ogg");
+        std::fs::write(&path, BOWL_SOUND).unwrap();
+        assert_eq!(sound_file_duration(&path), bowl_duration());
+        assert_eq!(sound_file_duration(&path), bowl_duration());
+
+        // Same path, new contents and modification time
+        let file = File::create(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        drop(file);
+        assert_eq!(sound_file_duration(&path), None);
+    }
+    #[test]
+    fn seeded_volume_variation_is_repeatable() {
+        let config = Config {
+            volume: 60,
+            volume_variation: 20,
+            jitter_seed: Some(42),
+            ..Config::default()
+        };
+        let volumes = |config: &Config| {
+            let mut rng = volume_rng(config);
+            (0..50)
+                .map(|_| vary_volume(config.volume, config.volume_variation, &mut rng))
+                .collect::<Vec<_>>()
+        };
+
+        let first = volumes(&config);
+        assert_eq!(first, volumes(&config));
+        assert!(first.iter().all(|v| (48..=72).contains(v)), "{:?}", first);
+        assert!(first.iter().any(|&v| v != first[0]), "{:?}", first);
+    }
+
+    #[test]
+    fn volume_variation_stays_in_range() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert_eq!(vary_volume(60, 0, &mut rng), 60);
+        assert_eq!(vary_volume(0, 50, &mut rng), 0);
+        assert!((0..200).all(|_| vary_volume(100, 50, &mut rng) <= 100));
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use ical::parser::ical::component::IcalEvent;
+use ical::IcalParser;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use tracing::{debug, warn};
+
+/// A busy interval read from the calendar
+#[derive(Debug, Clone)]
+struct BusyEvent {
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+}
+
+/// Local ICS calendar used to suppress bells during busy events.
+///
+/// The file is re-read whenever its modification time changes. Recurring
+/// events are not expanded, and `TZID` parameters are ignored, so times
+/// without a trailing `Z` are taken as local time.
+pub struct Calendar {
+    path: PathBuf,
+    events: Vec<BusyEvent>,
+    modified: Option<SystemTime>,
+}
+
+impl Calendar {
+    pub fn new(path: PathBuf) -> Self {
+        Self {
+            path,
+            events: Vec::new(),
+            modified: None,
+        }
+    }
+
+    /// Whether a busy event covers `now`
+    pub fn is_busy(&mut self, now: DateTime<Local>) -> bool {
+        self.refresh();
+        self.events.iter().any(|e| e.start <= now && now < e.end)
+    }
+
+    fn refresh(&mut self) {
+        let modified = match std::fs::metadata(&self.path).and_then(|m| m.modified()) {
+            Ok(m) => m,
+            Err(e) => {
+                if self.modified.take().is_some() || !self.events.is_empty() {
+                    warn!("Failed to read calendar {:?}: {}", self.path, e);
+                }
+                self.events.clear();
+                return;
+            }
+        };
+
+        if self.modified == Some(modified) {
+            return;
+        }
+        self.modified = Some(modified);
+
+        let file = match File::open(&self.path) {
+            Ok(f) => f,
+            Err(e) => {
+                warn!("Failed to open calendar {:?}: {}", self.path, e);
+                self.events.clear();
+                return;
+            }
+        };
+
+        self.events = parse_events(BufReader::new(file));
+        debug!(
+            "Loaded {} busy events from {:?}",
+            self.events.len(),
+            self.path
+        );
+    }
+}
+
+fn parse_events<B: std::io::BufRead>(reader: B) -> Vec<BusyEvent> {
+    let mut events = Vec::new();
+
+    for calendar in IcalParser::new(reader) {
+        let calendar = match calendar {
+            Ok(c) => c,
+            Err(e) => {
+                warn!("Ignoring malformed calendar data: {}", e);
+                continue;
+            }
+        };
+
+        events.extend(calendar.events.iter().filter_map(busy_event));
+    }
+
+    events
+}
+
+/// Convert an event to a busy interval, skipping free, cancelled, or malformed ones
+fn busy_event(event: &IcalEvent) -> Option<BusyEvent> {
+    let property = |name: &str| event.properties.iter().find(|p| p.name == name);
+    let value = |name: &str| property(name).and_then(|p| p.value.as_deref());
+
+    if value("TRANSP") == Some("TRANSPARENT") || value("STATUS") == Some("CANCELLED") {
+        return None;
+    }
+
+    let start_value = value("DTSTART")?;
+    let Some((start, all_day)) = parse_datetime(start_value) else {
+        warn!("Ignoring calendar event with invalid DTSTART: {}", start_value);
+        return None;
+    };
+
+    let end = match value("DTEND") {
+        Some(end_value) => match parse_datetime(end_value) {
+            Some((end, _)) => end,
+            None => {
+                warn!("Ignoring calendar event with invalid DTEND: {}", end_value);
+                return None;
+            }
+        },
+        // Without an end, an all-day event lasts the day and a timed one is instantaneous
+        None if all_day => start + chrono::Duration::days(1),
+        None => start,
+    };
+
+    (start < end).then_some(BusyEvent { start, end })
+}
+
+/// Parse an ICS date or date-time, returning whether it was a whole-day date
+fn parse_datetime(value: &str) -> Option<(DateTime<Local>, bool)> {
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((Utc.from_utc_datetime(&naive).with_timezone(&Local), false));
+    }
+
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        return Local
+            .from_local_datetime(&naive)
+            .earliest()
+            .map(|dt| (dt, false));
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y%m%d").ok()?;
+    Local
+        .from_local_datetime(&date.and_hms_opt(0, 0, 0)?)
+        .earliest()
+        .map(|dt| (dt, true))
+}
@@ -0,0 +1,94 @@
+use chrono::{DateTime, Local};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Source of the current time for the daemon, stats and scheduler, so
+/// streaks, quiet hours and day rollover can be exercised without waiting
+pub trait Clock: Send + Sync {
+    /// Monotonic time, for intervals and timeouts
+    fn now_instant(&self) -> Instant;
+    /// Wall-clock time, for dates, weekdays and quiet hours
+    fn now_local(&self) -> DateTime<Local>;
+}
+
+/// The real system clocks
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn now_local(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// A clock that only moves when told to, for tests and benchmarks. Clones
+/// share the same time.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<(Instant, DateTime<Local>)>>,
+}
+
+impl MockClock {
+    /// A clock reading `start` on the wall clock
+    pub fn new(start: DateTime<Local>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new((Instant::now(), start))),
+        }
+    }
+
+    /// Move both readings forward by `by`
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        now.0 += by;
+        now.1 += chrono::Duration::from_std(by).unwrap_or(chrono::Duration::MAX);
+    }
+
+    /// Jump the wall clock to `at` without moving monotonic time, like a
+    /// manual clock change or an NTP correction
+    pub fn set_local(&self, at: DateTime<Local>) {
+        self.now.lock().unwrap_or_else(|e| e.into_inner()).1 = at;
+    }
+}
+
+impl Clock for MockClock {
+    fn now_instant(&self) -> Instant {
+        self.now.lock().unwrap_or_else(|e| e.into_inner()).0
+    }
+
+    fn now_local(&self) -> DateTime<Local> {
+        self.now.lock().unwrap_or_else(|e| e.into_inner()).1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn advance_moves_both_readings() {
+        let start = Local.with_ymd_and_hms(2026, 1, 5, 8, 0, 0).unwrap();
+        let clock = MockClock::new(start);
+        let instant = clock.now_instant();
+
+        clock.clone().advance(Duration::from_secs(90));
+        assert_eq!(clock.now_instant() - instant, Duration::from_secs(90));
+        assert_eq!(clock.now_local() - start, chrono::Duration::seconds(90));
+    }
+
+    #[test]
+    fn set_local_leaves_monotonic_time() {
+        let start = Local.with_ymd_and_hms(2026, 1, 5, 8, 0, 0).unwrap();
+        let clock = MockClock::new(start);
+        let instant = clock.now_instant();
+
+        let earlier = Local.with_ymd_and_hms(2026, 1, 4, 23, 0, 0).unwrap();
+        clock.set_local(earlier);
+        assert_eq!(clock.now_local(), earlier);
+        assert_eq!(clock.now_instant(), instant);
+    }
+}
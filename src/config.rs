@@ -1,18 +1,218 @@
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use chrono::{NaiveTime, Timelike, Weekday};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
+use std::time::{Duration, SystemTime};
 use thiserror::Error;
+use tracing::{debug, warn};
 
 static PROJECT_DIRS: OnceLock<Option<ProjectDirs>> = OnceLock::new();
 
+/// Age after which a config temp file is taken to be left by an interrupted
+/// save rather than one in progress
+const STALE_TEMP_AGE: Duration = Duration::from_secs(10);
+
+/// Highest `max_bells_per_day`: a bell every minute, all day
+const MAX_BELLS_PER_DAY: u64 = 24 * 60;
+
+/// Most strikes per bell; higher `repeat` values are clamped to it
+pub const MAX_REPEAT: u8 = 10;
+
+/// Longest silence between strikes, in seconds, for `repeat_gap_secs` and
+/// `opening_burst.gap_secs`
+const MAX_GAP_SECS: f32 = 60.0;
+
+/// Temp files left by saves of the config at `path` more than
+/// `STALE_TEMP_AGE` ago, oldest first
+fn stale_temp_files(path: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (path.parent(), path.file_name()) else {
+        return Vec::new();
+    };
+    let prefix = format!("{}.", name.to_string_lossy());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let now = SystemTime::now();
+    let mut stale: Vec<(SystemTime, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            name.starts_with(&prefix) && name.ends_with(".tmp")
+        })
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .filter(|(modified, _)| {
+            now.duration_since(*modified)
+                .is_ok_and(|age| age >= STALE_TEMP_AGE)
+        })
+        .collect();
+    stale.sort();
+    stale.into_iter().map(|(_, path)| path).collect()
+}
+
 fn get_project_dirs() -> Option<&'static ProjectDirs> {
     PROJECT_DIRS
         .get_or_init(|| ProjectDirs::from("", "", "mbell"))
         .as_ref()
 }
 
+/// Source of screen lock events, tried in the configured order
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LockBackend {
+    /// systemd-logind session Lock/Unlock signals (system bus)
+    Logind,
+    /// org.freedesktop.ScreenSaver ActiveChanged signal (session bus)
+    Screensaver,
+    /// No automatic detection, only `mbell away`
+    Manual,
+}
+
+impl std::fmt::Display for LockBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockBackend::Logind => write!(f, "logind"),
+            LockBackend::Screensaver => write!(f, "screensaver"),
+            LockBackend::Manual => write!(f, "manual"),
+        }
+    }
+}
+
+/// Where the daemon's log goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogBackend {
+    /// Standard error when running in the foreground, plus `log_file` if set
+    #[default]
+    Stderr,
+    /// Only `log_file`, even in the foreground
+    File,
+    /// The systemd journal with structured fields, plus `log_file` if set
+    Journald,
+}
+
+impl std::fmt::Display for LogBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogBackend::Stderr => write!(f, "stderr"),
+            LogBackend::File => write!(f, "file"),
+            LogBackend::Journald => write!(f, "journald"),
+        }
+    }
+}
+
+/// Source of idle events for `idle_timeout_secs`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IdleBackend {
+    /// Wayland when `WAYLAND_DISPLAY` is set, falling back to screensaver
+    #[default]
+    Auto,
+    /// The compositor's ext-idle-notify-v1 protocol
+    Wayland,
+    /// Polling org.freedesktop.ScreenSaver GetSessionIdleTime (session bus)
+    Screensaver,
+}
+
+impl std::fmt::Display for IdleBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IdleBackend::Auto => write!(f, "auto"),
+            IdleBackend::Wayland => write!(f, "wayland"),
+            IdleBackend::Screensaver => write!(f, "screensaver"),
+        }
+    }
+}
+
+/// Kind of Unix socket the daemon listens on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SocketKind {
+    /// A socket file in the runtime dir
+    #[default]
+    File,
+    /// A name in Linux's abstract namespace, for when no runtime dir is writable
+    Abstract,
+}
+
+impl std::fmt::Display for SocketKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SocketKind::File => write!(f, "file"),
+            SocketKind::Abstract => write!(f, "abstract"),
+        }
+    }
+}
+
+/// Parse a duration such as "20", "20m", "1h", "1h30m" or "90s" into whole
+/// minutes. A bare number is taken as minutes.
+pub fn parse_minutes(input: &str) -> Result<u64, String> {
+    let input = input.trim();
+    if let Ok(mins) = input.parse::<u64>() {
+        return Ok(mins);
+    }
+
+    let mut total_secs: u64 = 0;
+    let mut digits = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let unit_secs = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(format!("invalid duration {:?}: unknown unit '{}'", input, c)),
+        };
+        let value: u64 = digits
+            .parse()
+            .map_err(|_| format!("invalid duration {:?}: expected a number before '{}'", input, c))?;
+        total_secs = value
+            .checked_mul(unit_secs)
+            .and_then(|secs| total_secs.checked_add(secs))
+            .ok_or_else(|| format!("invalid duration {:?}: too large", input))?;
+        digits.clear();
+    }
+
+    if !digits.is_empty() || input.is_empty() {
+        return Err(format!(
+            "invalid duration {:?}: expected e.g. \"20m\", \"1h\" or \"1h30m\"",
+            input
+        ));
+    }
+    if !total_secs.is_multiple_of(60) {
+        return Err(format!("invalid duration {:?}: must be whole minutes", input));
+    }
+    Ok(total_secs / 60)
+}
+
+fn deserialize_minutes<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Minutes {
+        Number(u64),
+        Text(String),
+    }
+
+    match Minutes::deserialize(deserializer)? {
+        Minutes::Number(mins) => Ok(mins),
+        Minutes::Text(text) => parse_minutes(&text).map_err(serde::de::Error::custom),
+    }
+}
+
+fn deserialize_opt_minutes<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u64>, D::Error> {
+    deserialize_minutes(deserializer).map(Some)
+}
+
 #[derive(Error, Debug)]
 pub enum ConfigError {
     #[error("Failed to determine config directory")]
@@ -23,33 +223,468 @@ pub enum ConfigError {
     ParseError(#[from] toml::de::Error),
     #[error("Invalid configuration: {0}")]
     ValidationError(String),
+    #[error("Unknown profile: {0}")]
+    UnknownProfile(String),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
-    /// Interval between bells in minutes
+    /// How bells are scheduled: a fixed interval or pomodoro phases
+    pub mode: Mode,
+    /// Phase lengths used when `mode` is pomodoro
+    pub pomodoro: Pomodoro,
+    /// Interval between bells in minutes. Also accepts a duration string
+    /// such as "20m", "1h" or "1h30m".
+    #[serde(deserialize_with = "deserialize_minutes")]
     pub interval: u64,
-    /// Volume level (0-100)
+    /// Intervals for particular weekdays, in place of `interval`
+    pub schedule: Option<Schedule>,
+    /// Random offset of up to this many seconds either way on each interval
+    pub jitter_secs: u64,
+    /// Seed for the jitter and volume variation, for a repeatable sequence
+    pub jitter_seed: Option<u64>,
+    /// Volume level (0-100); 0 skips audio but keeps scheduling and stats
     pub volume: u8,
+    /// Random change to each bell's volume, up to this percent of it either way
+    pub volume_variation: u8,
+    /// Custom bell sound; the embedded bowl is used when unset or unreadable
+    pub sound_path: Option<PathBuf>,
+    /// Sounds for the bell, pause, resume and daemon startup
+    pub sounds: Option<Sounds>,
+    /// Output device to play on, matched by part of its name
+    pub audio_device: Option<String>,
+    /// Sound looped quietly for the whole session, dipping out for each bell
+    pub ambient_path: Option<PathBuf>,
+    /// Volume of the ambient sound (0-100)
+    pub ambient_volume: u8,
+    /// Strikes per bell, clamped to 1-`MAX_REPEAT`
+    pub repeat: u8,
+    /// Seconds before each scheduled bell to play a soft warning strike;
+    /// 0 disables it
+    pub pre_bell_secs: u64,
+    /// Seconds of silence between strikes (0-60)
+    pub repeat_gap_secs: f32,
+    /// Pre-gain in decibels applied before volume, for quiet recordings
+    pub gain_db: f32,
+    /// Milliseconds over which the sound fades in from silence
+    pub fade_in_ms: u64,
+    /// Milliseconds over which the end of the sound fades out to silence
+    pub fade_out_ms: u64,
     /// Log level: error, warn, info, debug, trace
     pub log_level: String,
+    /// Where to log: stderr, file or journald
+    pub log_backend: LogBackend,
+    /// File to log to as well, rotated daily
+    pub log_file: Option<PathBuf>,
+    /// Rotated log files to keep
+    pub log_max_files: Option<usize>,
+    /// Only count time while the user is present toward the next bell
+    pub require_presence: bool,
+    /// Seconds without input after which the user counts as away
+    pub presence_idle_secs: u64,
+    /// Seconds without input after which the bell pauses until input
+    /// resumes; read at startup
+    pub idle_timeout_secs: Option<u64>,
+    /// Where idle events for `idle_timeout_secs` come from; read at startup
+    pub idle_backend: IdleBackend,
+    /// Ring straight away when the bell resumes after a pause or screen lock,
+    /// instead of starting a fresh interval
+    pub ring_on_resume: bool,
+    /// Ring once when the daemon starts, as an audio check
+    pub ring_on_start: bool,
+    /// Count the `ring_on_start` bell in the session and stats
+    pub count_start_bell: bool,
+    /// Kind of IPC socket; `MBELL_SOCKET` overrides it. Read at startup.
+    pub socket: SocketKind,
+    /// Permissions for the IPC socket, as an octal string
+    pub socket_mode: String,
+    /// Lock detection backends to try, in order
+    pub lock_backends: Vec<LockBackend>,
+    /// Hold the bell while the screen is locked
+    pub pause_on_lock: bool,
+    /// ICS calendar whose busy events suppress the bell
+    pub calendar_path: Option<PathBuf>,
+    /// Play a celebration chime when a new longest streak is reached
+    pub celebrate_records: bool,
+    /// Show a desktop notification with each bell
+    pub notify: bool,
+    /// Notification text; `{count}` is the session bell number and
+    /// `{message}` the bell's entry from `messages`
+    pub notify_body: String,
+    /// Phrases attached to bells in turn; empty disables messages
+    pub messages: Vec<String>,
+    /// Announce each bell's number in the session once it has rung
+    pub announce_count: bool,
+    /// Command announcing the count, e.g. via `spd-say`; `{count}` is the
+    /// session bell number. Without one, the bowl strikes the count instead.
+    pub announce_command: Option<String>,
+    /// Quick strikes played once when the daemon starts
+    pub opening_burst: Option<OpeningBurst>,
+    /// Make the bell more insistent after repeated snoozes
+    pub snooze_escalation: Option<SnoozeEscalation>,
+    /// Stop playing audio once the session has settled in
+    pub audio_ramp: Option<AudioRamp>,
+    /// Bell volume rising (or falling) over the first bells of a session
+    pub volume_ramp: Option<VolumeRamp>,
+    /// Local time to log a summary of the day's practice, e.g. "21:00"
+    pub daily_summary_at: Option<NaiveTime>,
+    /// Back up the stats file daily, keeping this many backups
+    pub stats_backup_keep: Option<usize>,
+    /// Pause after this many minutes without any command or lock event
+    pub auto_pause_after_mins: Option<u64>,
+    /// Stop scheduled bells for the rest of the day after this many bells;
+    /// 0 for no limit
+    pub max_bells_per_day: u64,
+    /// Fsync the stats file and its directory after every save
+    pub durable_stats: bool,
+    /// Finished sessions kept in stats for `mbell stats --sessions`
+    pub session_history_keep: usize,
+    /// Local time a new day starts for streaks and daily counts, e.g. "04:00"
+    pub day_boundary: NaiveTime,
+    /// Daily window during which scheduled bells stay silent
+    pub quiet_hours: Option<QuietHours>,
+    /// Also refuse `mbell ring` during quiet hours
+    pub quiet_hours_block_manual: bool,
+    /// Shortest time between manual rings, in milliseconds; 0 for no limit
+    pub min_ring_gap_ms: u64,
+    /// Profile applied at startup
+    pub default_profile: Option<String>,
+    /// Named sets of overrides, switchable at runtime with `mbell profile`
+    pub profiles: BTreeMap<String, Profile>,
+    /// Named extra bells at fixed minutes of the hour, alongside the interval
+    pub patterns: BTreeMap<String, Pattern>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// A bell every `interval` minutes
+    #[default]
+    Interval,
+    /// Alternating work and break phases, with a bell at each transition
+    Pomodoro,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Pomodoro {
+    /// Minutes of focused work
+    pub work_mins: u64,
+    /// Minutes of a short break
+    pub break_mins: u64,
+    /// Minutes of a long break
+    pub long_break_mins: u64,
+    /// Work phases before a long break instead of a short one
+    pub cycles_before_long_break: u32,
+}
+
+impl Default for Pomodoro {
+    fn default() -> Self {
+        Self {
+            work_mins: 25,
+            break_mins: 5,
+            long_break_mins: 15,
+            cycles_before_long_break: 4,
+        }
+    }
+}
+
+/// Interval per weekday; days left out use `default`, then `interval`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Schedule {
+    #[serde(deserialize_with = "deserialize_opt_minutes")]
+    pub default: Option<u64>,
+    #[serde(deserialize_with = "deserialize_opt_minutes")]
+    pub monday: Option<u64>,
+    #[serde(deserialize_with = "deserialize_opt_minutes")]
+    pub tuesday: Option<u64>,
+    #[serde(deserialize_with = "deserialize_opt_minutes")]
+    pub wednesday: Option<u64>,
+    #[serde(deserialize_with = "deserialize_opt_minutes")]
+    pub thursday: Option<u64>,
+    #[serde(deserialize_with = "deserialize_opt_minutes")]
+    pub friday: Option<u64>,
+    #[serde(deserialize_with = "deserialize_opt_minutes")]
+    pub saturday: Option<u64>,
+    #[serde(deserialize_with = "deserialize_opt_minutes")]
+    pub sunday: Option<u64>,
+}
+
+impl Schedule {
+    /// Interval for `day`, falling back to `default`
+    pub fn interval_for(&self, day: Weekday) -> Option<u64> {
+        let interval = match day {
+            Weekday::Mon => self.monday,
+            Weekday::Tue => self.tuesday,
+            Weekday::Wed => self.wednesday,
+            Weekday::Thu => self.thursday,
+            Weekday::Fri => self.friday,
+            Weekday::Sat => self.saturday,
+            Weekday::Sun => self.sunday,
+        };
+        interval.or(self.default)
+    }
+
+    /// Each set entry with its key, in week order
+    pub fn entries(&self) -> impl Iterator<Item = (&'static str, u64)> {
+        [
+            ("default", self.default),
+            ("monday", self.monday),
+            ("tuesday", self.tuesday),
+            ("wednesday", self.wednesday),
+            ("thursday", self.thursday),
+            ("friday", self.friday),
+            ("saturday", self.saturday),
+            ("sunday", self.sunday),
+        ]
+        .into_iter()
+        .filter_map(|(day, mins)| mins.map(|mins| (day, mins)))
+    }
+}
+
+/// Overrides applied on top of the base config when a profile is active
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// Interval in minutes or as a duration string
+    #[serde(deserialize_with = "deserialize_opt_minutes")]
+    pub interval: Option<u64>,
+    pub volume: Option<u8>,
+    pub gain_db: Option<f32>,
+}
+
+/// Scheduled bells are audible until either limit is reached, then silent
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioRamp {
+    /// Bells this session after which audio stops
+    pub after_bells: Option<u64>,
+    /// Minutes since the daemon started after which audio stops
+    pub after_mins: Option<u64>,
+}
+
+/// Bells move in even steps from `start` to `end` volume, then stay there
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VolumeRamp {
+    /// Volume of the session's first bell (0-100)
+    pub start: u8,
+    /// Volume from bell `over_bells` on (0-100)
+    pub end: u8,
+    /// Bells the ramp takes, the first and last included
+    pub over_bells: u64,
+}
+
+impl VolumeRamp {
+    /// Volume for a bell after `rung` bells this session
+    pub fn volume_after(&self, rung: u64) -> u8 {
+        let steps = self.over_bells.saturating_sub(1);
+        if rung >= steps {
+            return self.end.min(100);
+        }
+        let (start, end) = (self.start as f64, self.end as f64);
+        let volume = start + (end - start) * rung as f64 / steps as f64;
+        volume.round().clamp(0.0, 100.0) as u8
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SnoozeEscalation {
+    /// Percent each consecutive snooze is shortened by
+    pub interval_step_pct: u32,
+    /// Volume points added to a snoozed bell per consecutive snooze
+    pub volume_step: u8,
+    /// Consecutive snoozes after which escalation stops growing
+    pub max_steps: u32,
+}
+
+impl Default for SnoozeEscalation {
+    fn default() -> Self {
+        Self {
+            interval_step_pct: 20,
+            volume_step: 10,
+            max_steps: 3,
+        }
+    }
+}
+
+/// A sound per event. Entries left out play the embedded bowl; `bell` falls
+/// back to `sound_path` first.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Sounds {
+    /// Scheduled and manual bells
+    pub bell: Option<PathBuf>,
+    /// `mbell pause`
+    pub pause: Option<PathBuf>,
+    /// `mbell resume`
+    pub resume: Option<PathBuf>,
+    /// The daemon starting a session
+    pub session_start: Option<PathBuf>,
+}
+
+/// A bell rung at a fixed minute of the hour, independent of the interval
+/// timer, e.g. three strikes on the hour
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Pattern {
+    /// Minute past the hour (0-59)
+    pub minute: u32,
+    /// Hours of the day it rings in (0-23); every hour when empty
+    #[serde(default)]
+    pub hours: Vec<u32>,
+    /// Strikes of the bell (1-10)
+    pub repeat: u8,
+}
+
+impl Pattern {
+    /// Whether the pattern rings in the minute containing `time`
+    pub fn matches(&self, time: NaiveTime) -> bool {
+        time.minute() == self.minute && (self.hours.is_empty() || self.hours.contains(&time.hour()))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// Local time the window opens, e.g. "22:00"
+    pub start: NaiveTime,
+    /// Local time the window closes, e.g. "07:00"; may be before `start`
+    pub end: NaiveTime,
+}
+
+impl QuietHours {
+    /// Whether `time` falls inside the window, which may cross midnight
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start <= self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpeningBurst {
+    /// Number of strikes (1-10)
+    pub count: u32,
+    /// Seconds between strikes (0-60)
+    pub gap_secs: f32,
+    /// Record each strike as a bell in the statistics
+    pub count_in_stats: bool,
+}
+
+impl Default for OpeningBurst {
+    fn default() -> Self {
+        Self {
+            count: 3,
+            gap_secs: 2.0,
+            count_in_stats: false,
+        }
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            mode: Mode::Interval,
+            pomodoro: Pomodoro::default(),
             interval: 10,
+            schedule: None,
+            jitter_secs: 0,
+            jitter_seed: None,
             volume: 70,
+            volume_variation: 0,
+            sound_path: None,
+            sounds: None,
+            audio_device: None,
+            ambient_path: None,
+            ambient_volume: 20,
+            repeat: 1,
+            pre_bell_secs: 0,
+            repeat_gap_secs: 2.0,
+            gain_db: 0.0,
+            fade_in_ms: 0,
+            fade_out_ms: 0,
             log_level: "info".to_string(),
+            log_backend: LogBackend::Stderr,
+            log_file: None,
+            log_max_files: None,
+            require_presence: false,
+            presence_idle_secs: 60,
+            idle_timeout_secs: None,
+            idle_backend: IdleBackend::Auto,
+            ring_on_resume: false,
+            ring_on_start: false,
+            count_start_bell: false,
+            socket: SocketKind::File,
+            socket_mode: "0600".to_string(),
+            lock_backends: vec![
+                LockBackend::Logind,
+                LockBackend::Screensaver,
+                LockBackend::Manual,
+            ],
+            pause_on_lock: true,
+            calendar_path: None,
+            celebrate_records: false,
+            notify: false,
+            notify_body: "Bell {count} this session".to_string(),
+            messages: Vec::new(),
+            announce_count: false,
+            announce_command: None,
+            opening_burst: None,
+            snooze_escalation: None,
+            audio_ramp: None,
+            volume_ramp: None,
+            daily_summary_at: None,
+            stats_backup_keep: None,
+            auto_pause_after_mins: None,
+            max_bells_per_day: 0,
+            durable_stats: false,
+            session_history_keep: 100,
+            day_boundary: NaiveTime::MIN,
+            quiet_hours: None,
+            quiet_hours_block_manual: false,
+            min_ring_gap_ms: 0,
+            default_profile: None,
+            profiles: BTreeMap::new(),
+            patterns: BTreeMap::new(),
         }
     }
 }
 
 impl Config {
     pub fn load() -> Result<Self, ConfigError> {
+        Self::load_checked(false)
+    }
+
+    /// Load the config, with `decode_sounds` also decoding each sound file to
+    /// confirm its format is supported. Only existence is checked otherwise.
+    pub fn load_checked(decode_sounds: bool) -> Result<Self, ConfigError> {
         let path = Self::config_path()?;
 
+        // Recover the config from an interrupted save if it's missing, and
+        // clean up after any others. Recent temp files are left alone, as
+        // another process may be about to rename them.
+        let mut stale = stale_temp_files(&path);
+        if !path.exists() {
+            if let Some(newest) = stale.pop() {
+                debug!("Found stale config temp file, attempting recovery");
+                if let Err(e) = fs::rename(&newest, &path) {
+                    warn!("Failed to recover config from temp file: {}", e);
+                    let _ = fs::remove_file(&newest);
+                } else {
+                    debug!("Recovered config from temp file");
+                }
+            }
+        }
+        for temp_path in stale {
+            debug!("Cleaning up stale config temp file {:?}", temp_path);
+            let _ = fs::remove_file(&temp_path);
+        }
+
         if !path.exists() {
             let config = Config::default();
             config.save()?;
@@ -57,8 +692,10 @@ impl Config {
         }
 
         let contents = fs::read_to_string(&path)?;
-        let config: Config = toml::from_str(&contents)?;
+        let mut config: Config = toml::from_str(&contents)?;
+        config.clamp_repeat();
         config.validate()?;
+        config.validate_sounds(decode_sounds)?;
         Ok(config)
     }
 
@@ -71,7 +708,22 @@ impl Config {
 
         let contents = toml::to_string_pretty(self)
             .map_err(|e| ConfigError::ValidationError(e.to_string()))?;
-        fs::write(&path, contents)?;
+
+        // Write atomically by writing to temp file first. The name is unique
+        // to this save, since the daemon and any CLI may save at once.
+        let temp_path = path.with_extension(format!(
+            "toml.{}.{:08x}.tmp",
+            std::process::id(),
+            rand::random::<u32>()
+        ));
+        let written = fs::File::create(&temp_path).and_then(|mut file| {
+            file.write_all(contents.as_bytes())?;
+            file.sync_all()
+        });
+        if let Err(e) = written.and_then(|()| fs::rename(&temp_path, &path)) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(e.into());
+        }
         Ok(())
     }
 
@@ -87,6 +739,15 @@ impl Config {
             .ok_or(ConfigError::NoConfigDir)
     }
 
+    /// Bring `repeat` into 1-`MAX_REPEAT` rather than refusing the config
+    fn clamp_repeat(&mut self) {
+        let repeat = self.repeat.clamp(1, MAX_REPEAT);
+        if repeat != self.repeat {
+            warn!("repeat = {} is out of range, using {}", self.repeat, repeat);
+            self.repeat = repeat;
+        }
+    }
+
     fn validate(&self) -> Result<(), ConfigError> {
         if self.interval == 0 {
             return Err(ConfigError::ValidationError(
@@ -100,6 +761,23 @@ impl Config {
             ));
         }
 
+        if self.volume_variation > 50 {
+            return Err(ConfigError::ValidationError(
+                "volume_variation must be between 0 and 50".to_string(),
+            ));
+        }
+        if self.ambient_volume > 100 {
+            return Err(ConfigError::ValidationError(
+                "ambient_volume must be between 0 and 100".to_string(),
+            ));
+        }
+
+        if !self.gain_db.is_finite() {
+            return Err(ConfigError::ValidationError(
+                "gain_db must be a finite number".to_string(),
+            ));
+        }
+
         let valid_levels = ["error", "warn", "info", "debug", "trace"];
         if !valid_levels.contains(&self.log_level.to_lowercase().as_str()) {
             return Err(ConfigError::ValidationError(format!(
@@ -108,19 +786,624 @@ impl Config {
             )));
         }
 
+        if self.log_file.as_ref().is_some_and(|path| path.file_name().is_none()) {
+            return Err(ConfigError::ValidationError(
+                "log_file must name a file".to_string(),
+            ));
+        }
+
+        if self.log_backend == LogBackend::File && self.log_file.is_none() {
+            return Err(ConfigError::ValidationError(
+                "log_backend = \"file\" requires log_file".to_string(),
+            ));
+        }
+
+        if self.log_max_files == Some(0) {
+            return Err(ConfigError::ValidationError(
+                "log_max_files must be greater than 0".to_string(),
+            ));
+        }
+
+        if self
+            .announce_command
+            .as_ref()
+            .is_some_and(|command| command.trim().is_empty())
+        {
+            return Err(ConfigError::ValidationError(
+                "announce_command must not be empty".to_string(),
+            ));
+        }
+
+        if self.max_bells_per_day > MAX_BELLS_PER_DAY {
+            return Err(ConfigError::ValidationError(format!(
+                "max_bells_per_day must be at most {} (one a minute), or 0 for no limit",
+                MAX_BELLS_PER_DAY
+            )));
+        }
+
+        if self.presence_idle_secs == 0 {
+            return Err(ConfigError::ValidationError(
+                "presence_idle_secs must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.idle_timeout_secs == Some(0) {
+            return Err(ConfigError::ValidationError(
+                "idle_timeout_secs must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.audio_device.as_ref().is_some_and(|name| name.trim().is_empty()) {
+            return Err(ConfigError::ValidationError(
+                "audio_device must not be empty".to_string(),
+            ));
+        }
+
+        if self.socket == SocketKind::Abstract && !cfg!(target_os = "linux") {
+            return Err(ConfigError::ValidationError(
+                "socket = \"abstract\" is only supported on Linux".to_string(),
+            ));
+        }
+
+        self.socket_mode_bits()?;
+
+        if self.lock_backends.is_empty() {
+            return Err(ConfigError::ValidationError(
+                "lock_backends must list at least one backend".to_string(),
+            ));
+        }
+
+        let pomodoro = &self.pomodoro;
+        if pomodoro.work_mins == 0
+            || pomodoro.break_mins == 0
+            || pomodoro.long_break_mins == 0
+            || pomodoro.cycles_before_long_break == 0
+        {
+            return Err(ConfigError::ValidationError(
+                "pomodoro phase lengths and cycles_before_long_break must be greater than 0"
+                    .to_string(),
+            ));
+        }
+
+        if !(0.0..=MAX_GAP_SECS).contains(&self.repeat_gap_secs) {
+            return Err(ConfigError::ValidationError(format!(
+                "repeat_gap_secs must be between 0 and {}",
+                MAX_GAP_SECS
+            )));
+        }
+
+        if let Some(burst) = &self.opening_burst {
+            if !(1..=10).contains(&burst.count) {
+                return Err(ConfigError::ValidationError(
+                    "opening_burst.count must be between 1 and 10".to_string(),
+                ));
+            }
+
+            if !(0.0..=MAX_GAP_SECS).contains(&burst.gap_secs) {
+                return Err(ConfigError::ValidationError(format!(
+                    "opening_burst.gap_secs must be between 0 and {}",
+                    MAX_GAP_SECS
+                )));
+            }
+        }
+
+        if let Some(escalation) = &self.snooze_escalation {
+            if escalation.interval_step_pct.saturating_mul(escalation.max_steps) > 90 {
+                return Err(ConfigError::ValidationError(
+                    "snooze_escalation.interval_step_pct * max_steps must be at most 90".to_string(),
+                ));
+            }
+        }
+
+        if let Some(ramp) = &self.audio_ramp {
+            if ramp.after_bells.is_none() && ramp.after_mins.is_none() {
+                return Err(ConfigError::ValidationError(
+                    "audio_ramp needs after_bells or after_mins".to_string(),
+                ));
+            }
+        }
+
+        if let Some(ramp) = &self.volume_ramp {
+            if ramp.start > 100 || ramp.end > 100 {
+                return Err(ConfigError::ValidationError(
+                    "volume_ramp start and end must be between 0 and 100".to_string(),
+                ));
+            }
+            if ramp.over_bells == 0 {
+                return Err(ConfigError::ValidationError(
+                    "volume_ramp.over_bells must be greater than 0".to_string(),
+                ));
+            }
+        }
+
+        if self.stats_backup_keep == Some(0) {
+            return Err(ConfigError::ValidationError(
+                "stats_backup_keep must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.session_history_keep == 0 {
+            return Err(ConfigError::ValidationError(
+                "session_history_keep must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.auto_pause_after_mins == Some(0) {
+            return Err(ConfigError::ValidationError(
+                "auto_pause_after_mins must be greater than 0".to_string(),
+            ));
+        }
+
+        if let Some(quiet) = &self.quiet_hours {
+            if quiet.start == quiet.end {
+                return Err(ConfigError::ValidationError(
+                    "quiet_hours start and end must differ".to_string(),
+                ));
+            }
+        }
+
+        if let Some(schedule) = &self.schedule {
+            if let Some((day, _)) = schedule.entries().find(|&(_, mins)| mins == 0) {
+                return Err(ConfigError::ValidationError(format!(
+                    "schedule.{} must be greater than 0",
+                    day
+                )));
+            }
+        }
+
+        for (name, profile) in &self.profiles {
+            if profile.interval == Some(0) {
+                return Err(ConfigError::ValidationError(format!(
+                    "profiles.{}.interval must be greater than 0",
+                    name
+                )));
+            }
+            if profile.volume.is_some_and(|v| v > 100) {
+                return Err(ConfigError::ValidationError(format!(
+                    "profiles.{}.volume must be between 0 and 100",
+                    name
+                )));
+            }
+            if profile.gain_db.is_some_and(|g| !g.is_finite()) {
+                return Err(ConfigError::ValidationError(format!(
+                    "profiles.{}.gain_db must be a finite number",
+                    name
+                )));
+            }
+        }
+
+        for (name, pattern) in &self.patterns {
+            if pattern.minute > 59 {
+                return Err(ConfigError::ValidationError(format!(
+                    "patterns.{}.minute must be between 0 and 59",
+                    name
+                )));
+            }
+            if pattern.hours.iter().any(|&hour| hour > 23) {
+                return Err(ConfigError::ValidationError(format!(
+                    "patterns.{}.hours must be between 0 and 23",
+                    name
+                )));
+            }
+            if !(1..=10).contains(&pattern.repeat) {
+                return Err(ConfigError::ValidationError(format!(
+                    "patterns.{}.repeat must be between 1 and 10",
+                    name
+                )));
+            }
+        }
+
+        if let Some(name) = &self.default_profile {
+            if !self.profiles.contains_key(name) {
+                return Err(ConfigError::ValidationError(format!(
+                    "default_profile {:?} is not defined under [profiles]",
+                    name
+                )));
+            }
+        }
+
         Ok(())
     }
 
+    /// Every configured sound file, with the key that sets it
+    pub fn sound_files(&self) -> Vec<(&'static str, &Path)> {
+        let mut files = vec![
+            ("sound_path", self.sound_path.as_deref()),
+            ("ambient_path", self.ambient_path.as_deref()),
+        ];
+        if let Some(sounds) = &self.sounds {
+            files.extend([
+                ("sounds.bell", sounds.bell.as_deref()),
+                ("sounds.pause", sounds.pause.as_deref()),
+                ("sounds.resume", sounds.resume.as_deref()),
+                ("sounds.session_start", sounds.session_start.as_deref()),
+            ]);
+        }
+        files
+            .into_iter()
+            .filter_map(|(key, path)| path.map(|path| (key, path)))
+            .collect()
+    }
+
+    /// Check that each sound file is a readable file and, with `decode`, that
+    /// its format is supported
+    fn validate_sounds(&self, decode: bool) -> Result<(), ConfigError> {
+        for (key, path) in self.sound_files() {
+            match fs::File::open(path).and_then(|file| file.metadata()) {
+                Ok(meta) if meta.is_file() => {}
+                Ok(_) => {
+                    return Err(ConfigError::ValidationError(format!(
+                        "{} {} is not a file",
+                        key,
+                        path.display()
+                    )));
+                }
+                Err(e) => {
+                    return Err(ConfigError::ValidationError(format!(
+                        "{} {}: {}",
+                        key,
+                        path.display(),
+                        e
+                    )));
+                }
+            }
+
+            if decode {
+                if let Err(e) = crate::audio::check_sound(path) {
+                    return Err(ConfigError::ValidationError(format!("{}: {}", key, e)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The `[patterns]` entry ringing in the minute containing `time`, the
+    /// one with the most strikes if several match
+    pub fn pattern_at(&self, time: NaiveTime) -> Option<(&str, &Pattern)> {
+        self.patterns
+            .iter()
+            .filter(|(_, pattern)| pattern.matches(time))
+            .max_by_key(|(_, pattern)| pattern.repeat)
+            .map(|(name, pattern)| (name.as_str(), pattern))
+    }
+
+    /// Whether `time` falls inside the configured quiet hours
+    pub fn in_quiet_hours(&self, time: NaiveTime) -> bool {
+        self.quiet_hours
+            .as_ref()
+            .is_some_and(|quiet| quiet.contains(time))
+    }
+
+    /// Interval in effect on `day`, from `[schedule]` if it covers the day
+    pub fn interval_on(&self, day: Weekday) -> u64 {
+        self.schedule
+            .as_ref()
+            .and_then(|schedule| schedule.interval_for(day))
+            .unwrap_or(self.interval)
+    }
+
+    /// This config with the named profile's overrides applied
+    pub fn with_profile(&self, name: &str) -> Result<Config, ConfigError> {
+        let profile = self
+            .profiles
+            .get(name)
+            .ok_or_else(|| ConfigError::UnknownProfile(name.to_string()))?;
+
+        let mut config = self.clone();
+        if let Some(interval) = profile.interval {
+            config.interval = interval;
+        }
+        if let Some(volume) = profile.volume {
+            config.volume = volume;
+        }
+        if let Some(gain_db) = profile.gain_db {
+            config.gain_db = gain_db;
+        }
+        Ok(config)
+    }
+
+    /// Parse `socket_mode`, requiring owner read/write so the CLI can connect
+    pub fn socket_mode_bits(&self) -> Result<u32, ConfigError> {
+        let invalid = || {
+            ConfigError::ValidationError(format!(
+                "socket_mode must be an octal mode between 0600 and 0777, got {:?}",
+                self.socket_mode
+            ))
+        };
+
+        let mode = u32::from_str_radix(&self.socket_mode, 8).map_err(|_| invalid())?;
+        if mode > 0o777 || mode & 0o600 != 0o600 {
+            return Err(invalid());
+        }
+        Ok(mode)
+    }
+
     pub fn default_config_contents() -> String {
-        r#"# Interval between bells in minutes
+        r#"# Scheduling: "interval" rings every interval; "pomodoro" alternates the
+# [pomodoro] work and break phases, ringing at each change
+mode = "interval"
+
+# Interval between bells in minutes, or a duration such as "20m" or "1h30m"
 interval = 10
 
-# Volume level (0-100)
+# Shift each interval by a random amount of up to this many seconds either way,
+# so the bell is harder to anticipate. Set jitter_seed for a repeatable sequence
+# (of volume_variation too).
+jitter_secs = 0
+# jitter_seed = 42
+
+# Volume level (0-100); 0 is silent mode, skipping audio entirely
 volume = 70
 
-# Log level: error, warn, info, debug, trace
+# Vary each bell's volume by a random amount of up to this percent of it either
+# way (0-50), like a real bowl struck by hand
+volume_variation = 0
+
+# Custom bell sound (OGG; FLAC, WAV and MP3 if built with the flac, wav, mp3
+# or symphonia features; see `mbell audio --formats`).
+# mbell start refuses a file that is missing or can't be decoded; if it goes
+# missing later, the embedded bowl plays instead.
+# sound_path = "/home/me/sounds/bowl.ogg"
+
+# Output device to play on, matched by part of its name (case-insensitive);
+# see `mbell audio --list-devices`. Uses the default device if unset or not found.
+# audio_device = "USB"
+
+# Loop this sound quietly for the whole session, dipping out while each bell
+# strikes; ambient_volume (0-100) sets its level. (optional)
+# ambient_path = "/home/me/sounds/rain.ogg"
+ambient_volume = 20
+
+# Strikes per bell (1-10, higher values are clamped) and the seconds of
+# silence between them (0-60)
+repeat = 1
+repeat_gap_secs = 2.0
+
+# Play a soft strike this many seconds before each scheduled bell, to settle
+# in; 0 disables it. Skipped when the bell itself won't sound (quiet hours,
+# daily cap, skip) and when the interval is shorter than this.
+pre_bell_secs = 0
+
+# Pre-gain in decibels (-30 to 12), boosts quiet recordings beyond volume 100
+gain_db = 0.0
+
+# Fade the sound in and out over this many milliseconds; 0 plays it as recorded.
+# Fades longer than the sound are shortened to fit.
+fade_in_ms = 0
+fade_out_ms = 0
+
+# Log level: error, warn, info, debug, trace. `mbell -v start` raises it a
+# level per -v; otherwise MBELL_LOG or RUST_LOG, if set, replace it with their
+# filter directives, e.g. RUST_LOG=mbell=debug,zbus=info.
 log_level = "info"
+
+# Where to log: "stderr" (when running in the foreground), "file" (only
+# log_file) or "journald" (the systemd journal, with structured fields such as
+# STATE and COUNT for journalctl filtering; falls back to stderr if unavailable)
+log_backend = "stderr"
+
+# Also log to this file, rotated daily to e.g. mbell.log.2026-01-31 and keeping
+# at most log_max_files of them. A detached daemon logs only here.
+# log_file = "/home/me/.local/state/mbell/mbell.log"
+# log_max_files = 7
+
+# Only ring after a full interval of presence; going idle restarts the count
+require_presence = false
+
+# Seconds without input after which you count as away
+presence_idle_secs = 60
+
+# Pause the bell after this many seconds without input, like a screen lock,
+# until you're back. idle_backend says how idle is detected: "wayland" asks the
+# compositor (ext-idle-notify), "screensaver" polls org.freedesktop.ScreenSaver
+# on the session bus, and "auto" tries Wayland first in a Wayland session.
+# idle_timeout_secs = 300
+idle_backend = "auto"
+
+# Ring as soon as the bell resumes after a pause or screen lock, rather than a
+# full interval later. Pomodoro phases pick up where they left off regardless.
+ring_on_resume = false
+
+# Ring once as the daemon starts, to confirm audio works. Silent in quiet hours
+# and past max_bells_per_day; counted as a bell only with count_start_bell.
+ring_on_start = false
+count_start_bell = false
+
+# IPC socket: "file" in the runtime dir, or "abstract" for Linux's abstract
+# namespace when the runtime dir isn't writable (e.g. some sandboxes). The
+# MBELL_SOCKET environment variable overrides this for daemon and CLI alike.
+socket = "file"
+
+# Permissions for the IPC socket (octal); 0600 keeps other users out.
+# Abstract sockets have no permissions; the daemon checks the peer's uid instead.
+socket_mode = "0600"
+
+# Lock detection backends to try in order: logind, screensaver, manual
+lock_backends = ["logind", "screensaver", "manual"]
+
+# Hold the bell while the screen is locked. Turn off to keep ringing through a
+# lock, e.g. on a wall-mounted display; `mbell away` still pauses either way.
+pause_on_lock = true
+
+# ICS calendar whose busy events suppress the bell
+# calendar_path = "/home/me/.local/share/calendar.ics"
+
+# Play a celebration chime when a new longest streak is reached
+celebrate_records = false
+
+# Show a desktop notification with each bell. In notify_body, {count} is the
+# session bell number and {message} the bell's entry from messages.
+notify = false
+notify_body = "Bell {count} this session"
+
+# Phrases attached to successive bells in turn, shown in the log and status
+# messages = ["Notice your breath", "Relax your shoulders"]
+
+# After each bell, announce its number this session. announce_command is run
+# with {count} filled in and killed if it takes over 30 seconds; without one,
+# the bowl strikes 1 to 5 times, counting the session in fives.
+announce_count = false
+# announce_command = "spd-say 'Bell {count}'"
+
+# Log a summary of the day's practice at this local time
+# daily_summary_at = "21:00"
+
+# Back up stats daily to the backups/ dir next to stats.json, keeping this many
+# stats_backup_keep = 7
+
+# Pause when no command or lock event has been seen for this many minutes,
+# until the next command; a backstop when lock and idle detection are unavailable
+# auto_pause_after_mins = 480
+
+# Stop scheduled bells for the rest of the day once this many bells have rung
+# today, counting manual rings; 0 for no limit. Resets at day_boundary.
+max_bells_per_day = 0
+
+# Fsync stats after every bell so none are lost on power failure (slower)
+durable_stats = false
+
+# Finished sessions kept for `mbell stats --sessions`, oldest dropped first
+session_history_keep = 100
+
+# Local time a new day starts for streaks, daily counts and the daily cap.
+# With "04:00", a bell at 1am still counts towards the previous day.
+day_boundary = "00:00"
+
+# Also refuse `mbell ring` during quiet hours (manual rings bypass them by default)
+quiet_hours_block_manual = false
+
+# Refuse `mbell ring` within this many milliseconds of the last one, so a script
+# ringing in a loop can't stack bells on top of each other; 0 allows any rate.
+# Scheduled bells aren't affected.
+min_ring_gap_ms = 0
+
+# Profile applied at startup, from the [profiles.<name>] tables below
+# default_profile = "work"
+
+# A sound for each event, played by the daemon. Once this table is present,
+# pause, resume and session start make a sound too; entries left out play the
+# embedded bowl, and bell falls back to sound_path first.
+# [sounds]
+# bell = "/home/me/sounds/bell.ogg"
+# pause = "/home/me/sounds/pause.ogg"
+# resume = "/home/me/sounds/resume.ogg"
+# session_start = "/home/me/sounds/gong.ogg"
+
+# Quick strikes played once when the daemon starts
+# [opening_burst]
+# count = 3
+# gap_secs = 2.0
+# count_in_stats = false
+
+# Make the bell more insistent after repeated snoozes
+# [snooze_escalation]
+# interval_step_pct = 20
+# volume_step = 10
+# max_steps = 3
+
+# Play audio only for the first bells of a session, then ring silently; the
+# first limit reached applies. Manual rings stay audible.
+# [audio_ramp]
+# after_bells = 3
+# after_mins = 60
+
+# Start the session's bells soft and raise them evenly over the first
+# over_bells bells, in place of volume; volume = 0 still silences them
+# [volume_ramp]
+# start = 30
+# end = 70
+# over_bells = 5
+
+# Scheduled bells stay silent in this daily window; it may cross midnight
+# [quiet_hours]
+# start = "22:00"
+# end = "07:00"
+
+# Intervals for particular weekdays; other days use default, then interval
+# [schedule]
+# default = 15
+# saturday = 30
+# sunday = 30
+
+# Pomodoro phase lengths, used with mode = "pomodoro"
+# [pomodoro]
+# work_mins = 25
+# break_mins = 5
+# long_break_mins = 15
+# cycles_before_long_break = 4
+
+# Named profiles overriding interval, volume, or gain_db; switch with `mbell profile <name>`
+# [profiles.work]
+# interval = "15m"
+# volume = 40
+
+# Extra bells at a fixed minute of the hour, alongside the interval. When one
+# falls in the same minute as the interval bell, only the pattern rings.
+# [patterns.hourly]
+# minute = 0
+# repeat = 3
+# hours = [9, 10, 11, 12, 13, 14, 15, 16, 17]  # every hour if left out
 "#
         .to_string()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeat_is_clamped() {
+        let mut config = Config {
+            repeat: 25,
+            ..Config::default()
+        };
+        config.clamp_repeat();
+        assert_eq!(config.repeat, MAX_REPEAT);
+        assert!(config.validate().is_ok());
+
+        config.repeat = 0;
+        config.clamp_repeat();
+        assert_eq!(config.repeat, 1);
+    }
+
+    #[test]
+    fn repeat_gap_is_bounded() {
+        for gap in [-1.0, 1e30, f32::INFINITY, f32::NAN] {
+            let config = Config {
+                repeat_gap_secs: gap,
+                ..Config::default()
+            };
+            assert!(config.validate().is_err(), "repeat_gap_secs = {}", gap);
+        }
+    }
+
+    #[test]
+    fn burst_gap_is_bounded() {
+        let config = Config {
+            opening_burst: Some(OpeningBurst {
+                gap_secs: 1e30,
+                ..OpeningBurst::default()
+            }),
+            ..Config::default()
+        };
+        assert!(config.validate().is_err());
+    }
+    #[test]
+    fn only_old_temp_files_are_stale() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let old = dir.path().join("config.toml.tmp");
+        let older = dir.path().join("config.toml.12.0000abcd.tmp");
+        let fresh = dir.path().join("config.toml.34.0000beef.tmp");
+        let other = dir.path().join("other.toml.tmp");
+        for (temp, age) in [(&old, 60), (&older, 120), (&fresh, 0), (&other, 60)] {
+            fs::File::create(temp)
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(age))
+                .unwrap();
+        }
+
+        assert_eq!(stale_temp_files(&path), [older, old]);
+    }
+}
@@ -1,18 +1,41 @@
-use crate::audio;
-use crate::config::Config;
-use crate::ipc::{Command, IpcServer, Response, StatusInfo};
+use crate::announce;
+use crate::audio::{self, AudioOutput, AudioPlayer, Sound};
+use crate::calendar::Calendar;
+use crate::clock::{Clock, SystemClock};
+use crate::config::{Config, ConfigError, Mode, OpeningBurst};
+use crate::idle::{start_idle_monitor, IdleEvent, IdleQuery};
+use crate::ipc::{
+    remove_stale_socket, socket_address, BellEvent, Command, CommandRequest, IpcServer, PidLock, Response,
+    SocketAddress, StatusInfo,
+};
 use crate::lock::{start_lock_monitor, LockEvent};
-use crate::stats::Stats;
+use crate::logging::LogLimiter;
+use crate::notify;
+use crate::schedule::{effective_config, startup_config, until_audible, Scheduler};
+use crate::state::SavedState;
+use crate::stats::{SessionRecord, Stats};
+use chrono::{DateTime, Datelike, Local, NaiveDate, NaiveDateTime, Timelike, Utc, Weekday};
+use rand::rngs::StdRng;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::task::JoinSet;
 use tokio::time::sleep;
-use tracing::{debug, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum DaemonState {
     Running,
     Paused,
     Locked,
+    /// No input for `idle_timeout_secs`
+    Idle,
+    /// Bell held back by an external source, e.g. a busy calendar event
+    Suppressed(&'static str),
 }
 
 impl std::fmt::Display for DaemonState {
@@ -21,96 +44,508 @@ impl std::fmt::Display for DaemonState {
             DaemonState::Running => write!(f, "running"),
             DaemonState::Paused => write!(f, "paused"),
             DaemonState::Locked => write!(f, "locked"),
+            DaemonState::Idle => write!(f, "idle"),
+            DaemonState::Suppressed(reason) => write!(f, "suppressed ({})", reason),
         }
     }
 }
 
+/// How long shutdown waits for IPC replies to be written
+const SHUTDOWN_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// How long shutdown keeps accepting connections that were already on their
+/// way, so their clients get an answer rather than a reset
+const SHUTDOWN_ACCEPT_GRACE: Duration = Duration::from_millis(50);
+
+/// How often idle time is sampled in `require_presence` mode
+const PRESENCE_CHECK_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Idle query failures, e.g. no ScreenSaver service, while they persist
+static IDLE_FAILURES: LogLimiter = LogLimiter::new(100, Duration::from_secs(3600));
+
+/// Number of recent bell times kept for `GetHistory`
+pub const HISTORY_CAPACITY: usize = 100;
+
+/// How often the calendar is checked for busy events
+const CALENDAR_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// How often the stats file is backed up when `stats_backup_keep` is set
+const STATS_BACKUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// How often accumulated running time is saved, bounding what a crash loses
+const ACTIVE_FLUSH_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// How often the clock is checked against `daily_summary_at`
+const SUMMARY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// How late the daily summary may still go out, e.g. after a suspend or a
+/// daemon restart just past `daily_summary_at`
+const SUMMARY_WINDOW: chrono::Duration = chrono::Duration::minutes(15);
+
+/// How often the weekday is checked for a `[schedule]` change
+const SCHEDULE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Suppression reason shown while auto-paused by `auto_pause_after_mins`
+const AUTO_PAUSE_REASON: &str = "auto-paused, no activity";
+
+pub use crate::schedule::PomodoroPhase;
+
 pub struct Daemon {
     config: Config,
+    calendar: Option<Calendar>,
     state: DaemonState,
     stats: Stats,
     bells_this_session: u64,
-    last_bell: Instant,
+    /// Bells rung on `today`, the local date, for `max_bells_per_day`
+    bells_today: u64,
+    today: NaiveDate,
+    /// Times of the most recent bells this session, oldest first. In memory
+    /// only, so it starts empty on every daemon restart.
+    history: VecDeque<DateTime<Utc>>,
+    /// Bell timer: interval, snooze, jitter, pomodoro phase and presence
+    scheduler: Scheduler,
+    /// Source of `volume_variation`, seeded from `jitter_seed` when set
+    volume_rng: StdRng,
+    /// Source of the current time
+    clock: Arc<dyn Clock>,
+    /// Let the next scheduled bell pass silently
+    skip_next: bool,
+    /// Ring on the next pass of the loop, for `ring_on_resume`
+    ring_pending: bool,
+    /// Due time of the bell the last pre-bell played for, so each bell gets
+    /// at most one
+    pre_bell_for: Option<Instant>,
+    /// Snoozes since the last regular (unsnoozed) bell, for escalation
+    consecutive_snoozes: u32,
+    idle_query: IdleQuery,
+    last_presence_check: Instant,
+    /// Start of the current stretch of running time not yet added to stats
+    active_since: Option<Instant>,
     was_paused_before_lock: bool,
+    /// Pause and snooze deadline as last saved for the next daemon, or
+    /// `None` when the saved state may not match
+    persisted: Option<(bool, Option<Instant>)>,
+    /// Stops the bell currently playing, if any
+    playback: Option<CancellationToken>,
+    /// Stats saves running in the background, awaited on shutdown so none
+    /// can land after the final save
+    pending_saves: JoinSet<()>,
+    /// Persistent audio output for bells and the ambient sound
+    output: AudioOutput,
+    /// Connections that asked for events with `Subscribe`
+    subscribers: Vec<mpsc::Sender<Response>>,
+    /// End of a commitment session, during which stop and pause are refused
+    committed_until: Option<Instant>,
+    /// Interval set at startup that takes precedence over the config file
+    interval_override: Option<u64>,
+    /// Config as loaded from disk, before the profile and override are applied
+    base_config: Config,
+    /// Name of the active profile, if any
+    profile: Option<String>,
+    /// Weekday whose `[schedule]` interval is applied
+    schedule_day: Weekday,
+    /// Local date the daily summary was last logged
+    last_summary: Option<NaiveDate>,
+    /// Local minute a `[patterns]` entry last matched, so each rings once
+    /// and the interval bell gives way to it
+    pattern_minute: Option<NaiveDateTime>,
+    /// Last command or lock event, for `auto_pause_after_mins`
+    last_interaction: Instant,
+    /// Last `mbell ring`, for `min_ring_gap_ms`
+    last_manual_ring: Option<Instant>,
+    /// Position in the `messages` rotation
+    message_index: usize,
+    /// When the current session started, for `audio_ramp` and its length
+    started_at: Instant,
+    /// Wall-clock start of the current session, for its record in stats
+    session_started: DateTime<Utc>,
 }
 
 impl Daemon {
-    pub fn new(config: Config) -> Self {
-        let stats = Stats::load().unwrap_or_default();
+    pub fn new(base_config: Config) -> Self {
+        Self::with_clock(base_config, Arc::new(SystemClock))
+    }
+
+    /// A daemon that reads the time from `clock` rather than the system
+    pub fn with_clock(base_config: Config, clock: Arc<dyn Clock>) -> Self {
+        let mut stats = Stats::load().unwrap_or_default();
+        // default_profile is checked against [profiles] when the config is loaded
+        let profile = base_config.default_profile.clone();
+        let started_at = clock.now_instant();
+        let schedule_day = clock.now_local().weekday();
+        let config = startup_config(&base_config, schedule_day);
+        audio::check_gain(config.volume, config.gain_db);
+        audio::check_fades(&config);
+        stats.set_durable(config.durable_stats);
+        stats.set_day_boundary(config.day_boundary);
+        // Pick up today's count so a restart doesn't reset the daily cap
+        let today = stats.day_of(clock.now_local());
+        let bells_today = stats.daily_counts.get(&today).copied().unwrap_or(0);
 
-        Self {
+        let scheduler = Scheduler::new(&config, started_at);
+        let volume_rng = audio::volume_rng(&config);
+
+        let mut daemon = Self {
+            calendar: config.calendar_path.clone().map(Calendar::new),
             config,
+            base_config,
+            profile,
+            schedule_day,
+            last_summary: None,
+            pattern_minute: None,
+            last_interaction: started_at,
+            last_manual_ring: None,
+            message_index: 0,
+            started_at,
+            session_started: clock.now_local().with_timezone(&Utc),
             state: DaemonState::Running,
             stats,
             bells_this_session: 0,
-            last_bell: Instant::now(),
+            bells_today,
+            today,
+            history: VecDeque::with_capacity(HISTORY_CAPACITY),
+            scheduler,
+            volume_rng,
+            clock,
+            skip_next: false,
+            ring_pending: false,
+            pre_bell_for: None,
+            consecutive_snoozes: 0,
+            idle_query: IdleQuery::new(),
+            last_presence_check: started_at,
+            active_since: None,
             was_paused_before_lock: false,
+            persisted: None,
+            playback: None,
+            pending_saves: JoinSet::new(),
+            output: AudioOutput::new(),
+            subscribers: Vec::new(),
+            committed_until: None,
+            interval_override: None,
+        };
+        daemon.restore_state();
+        daemon
+    }
+
+    /// Pick up a pause or snooze the previous daemon saved
+    fn restore_state(&mut self) {
+        let Some(saved) = SavedState::load() else {
+            return;
+        };
+        if saved.paused {
+            self.suspend_phase();
+            self.state = DaemonState::Paused;
+            info!(state = %self.state, "Still paused from before the restart");
         }
+        let now = self.clock.now_local().with_timezone(&Utc);
+        if let Some(left) = saved.snoozed_until.and_then(|until| (until - now).to_std().ok()) {
+            self.scheduler.snooze_until(self.clock.now_instant() + left);
+            info!(
+                "Still snoozed from before the restart, bell in {} minutes",
+                left.as_secs().div_ceil(60)
+            );
+        }
+        self.persisted = Some(self.state_to_persist());
+    }
+
+    /// Start running on a fresh interval, ignoring any state the previous
+    /// daemon saved
+    pub fn reset_state(&mut self) {
+        if self.state == DaemonState::Paused {
+            self.state = DaemonState::Running;
+        }
+        self.scheduler.restart(&self.config, self.clock.now_instant());
+        self.persisted = None;
+    }
+
+    /// Whether the bell is paused, counting a pause made or kept while
+    /// locked, and any snooze deadline
+    fn state_to_persist(&self) -> (bool, Option<Instant>) {
+        let paused = self.state == DaemonState::Paused
+            || (self.state == DaemonState::Locked && self.was_paused_before_lock);
+        (paused, self.scheduler.snooze_deadline())
+    }
+
+    /// Save the pause and snooze for the next daemon whenever they change
+    async fn persist_state(&mut self) {
+        let current = self.state_to_persist();
+        if self.persisted == Some(current) {
+            return;
+        }
+        self.persisted = Some(current);
+
+        let (paused, deadline) = current;
+        let left = deadline.map(|at| at.saturating_duration_since(self.clock.now_instant()));
+        let saved = SavedState {
+            paused,
+            snoozed_until: left.map(|left| {
+                self.clock.now_local().with_timezone(&Utc)
+                    + chrono::Duration::from_std(left).unwrap_or_default()
+            }),
+        };
+        if let Err(e) = saved.save().await {
+            warn!("Failed to save daemon state: {}", e);
+        }
+    }
+
+    /// Use `mins` as the interval for this run, including across reloads,
+    /// without touching the config file
+    pub fn override_interval(&mut self, mins: u64) {
+        self.interval_override = Some(mins);
+        self.config.interval = mins;
+    }
+
+    /// Refuse `Stop` and `Pause` over IPC for `duration`. Signals still stop
+    /// the daemon, as an escape hatch.
+    pub fn commit_for(&mut self, duration: Duration) {
+        self.committed_until = Some(self.clock.now_instant() + duration);
+    }
+
+    /// Replace the base config and active profile, returning whether the
+    /// effective interval changed. Leaves everything untouched on error.
+    fn apply_config(&mut self, base: Config, profile: Option<String>) -> Result<bool, ConfigError> {
+        let mut config = effective_config(&base, profile.as_deref(), self.schedule_day)?;
+        if let Some(mins) = self.interval_override {
+            config.interval = mins;
+        }
+
+        let interval_changed = config.interval != self.config.interval;
+        audio::check_gain(config.volume, config.gain_db);
+        audio::check_fades(&config);
+        if config.calendar_path != self.config.calendar_path {
+            self.calendar = config.calendar_path.clone().map(Calendar::new);
+        }
+        if config.ambient_path != self.config.ambient_path
+            || config.ambient_volume != self.config.ambient_volume
+            || config.audio_device != self.config.audio_device
+        {
+            self.output.set_ambient(&config);
+        }
+        self.stats.set_durable(config.durable_stats);
+        self.stats.set_day_boundary(config.day_boundary);
+        self.base_config = base;
+        self.profile = profile;
+        self.config = config;
+        Ok(interval_changed)
+    }
+
+    /// Time left in the commitment session, if one is active
+    fn commitment_remaining(&self) -> Option<Duration> {
+        self.committed_until
+            .map(|until| until.saturating_duration_since(self.clock.now_instant()))
+            .filter(|remaining| !remaining.is_zero())
     }
 
     pub async fn run(mut self) -> Result<(), Box<dyn std::error::Error>> {
         info!(
             "Daemon starting with interval of {} minutes",
-            self.config.interval
+            self.interval_duration().as_secs() / 60
         );
 
+        // Claim the pid lock before touching the socket, which may belong to
+        // another daemon. An abstract socket is its own lock: binding it fails
+        // while another daemon holds the name.
+        let pid_lock = match socket_address() {
+            SocketAddress::File(_) => Some(PidLock::acquire()?),
+            SocketAddress::Abstract(_) => None,
+        };
+        if let Some(lock) = &pid_lock {
+            if remove_stale_socket(lock) {
+                info!("Cleaned up after a daemon that didn't shut down cleanly");
+            }
+        }
+
         // Start IPC server
-        let ipc_server = IpcServer::new().await?;
-        let (cmd_tx, mut cmd_rx) = mpsc::channel::<(Command, mpsc::Sender<Response>)>(32);
+        let ipc_server = IpcServer::new(self.config.socket_mode_bits()?).await?;
+        let (cmd_tx, mut cmd_rx) = mpsc::channel::<CommandRequest>(32);
+        let mut connections = JoinSet::new();
+
+        // Find out once whether there's any audio output at all, rather than
+        // failing on every bell
+        self.output.probe(&self.config);
+
+        // Start the ambient sound, if configured, once we know we're the
+        // only daemon
+        if self.config.ambient_path.is_some() {
+            self.output.set_ambient(&self.config);
+        }
 
         // Start lock monitor
-        let (mut lock_rx, lock_handle) = start_lock_monitor();
+        let (mut lock_rx, lock_handle) = start_lock_monitor(self.config.lock_backends.clone());
+
+        // Start idle monitor, if configured
+        let idle_timeout = self.config.idle_timeout_secs.map(Duration::from_secs);
+        let (mut idle_rx, idle_handle) = match idle_timeout {
+            Some(timeout) => {
+                let (rx, handle) = start_idle_monitor(timeout, self.config.idle_backend);
+                (Some(rx), Some(handle))
+            }
+            None => (None, None),
+        };
 
         // Set up signal handlers
         let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())?;
         let mut sigint = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt())?;
+        let mut sighup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+
+        let mut calendar_tick = tokio::time::interval(CALENDAR_CHECK_INTERVAL);
+        let mut presence_tick = tokio::time::interval(PRESENCE_CHECK_INTERVAL);
+        let mut backup_tick = tokio::time::interval(STATS_BACKUP_INTERVAL);
+        let mut summary_tick = tokio::time::interval(SUMMARY_CHECK_INTERVAL);
+        let mut schedule_tick = tokio::time::interval(SCHEDULE_CHECK_INTERVAL);
+        let mut active_flush_tick = tokio::time::interval(ACTIVE_FLUSH_INTERVAL);
+        // The first tick completes immediately, with nothing to flush yet
+        active_flush_tick.tick().await;
+
+        self.play_event_sound(Sound::SessionStart);
+
+        if self.config.ring_on_start {
+            self.ring_start_bell().await;
+        }
 
-        // Bell interval duration
-        let interval_duration = Duration::from_secs(self.config.interval * 60);
+        if let Some(burst) = self.config.opening_burst.clone() {
+            self.play_opening_burst(&burst).await;
+        }
+
+        if let Some(duration) = audio::bowl_duration() {
+            debug!("Bell sound lasts {:.1}s", duration.as_secs_f32());
+        }
 
-        info!("Daemon running, first bell in {} minutes", self.config.interval);
+        info!("Daemon running, first bell in {} minutes", self.interval_duration().as_secs() / 60);
+        if let Some(remaining) = self.commitment_remaining() {
+            info!(
+                "Committed for {} minutes, stop and pause are refused until then",
+                remaining.as_secs().div_ceil(60)
+            );
+        }
 
         loop {
-            // Calculate time until next bell (only sleep when running)
-            let sleep_duration = if self.state == DaemonState::Running {
-                let elapsed = self.last_bell.elapsed();
-                interval_duration.saturating_sub(elapsed)
+            // State only changes inside the select below, so the time since
+            // the last pass was spent entirely in the state it started in
+            self.account_active_time();
+            self.persist_state().await;
+
+            // Calculate time until next bell (only armed when running). Derived
+            // from the current config each pass so a reload takes effect immediately.
+            // In presence mode the presence check rings the bell instead, unless snoozed.
+            let timer_active = self.state == DaemonState::Running
+                && (!self.config.require_presence
+                    || self.scheduler.is_snoozed()
+                    || self.ring_pending);
+            let sleep_duration = if self.ring_pending {
+                Duration::ZERO
             } else {
-                // When paused/locked, sleep for a long time; we'll be woken by other events
-                Duration::from_secs(3600)
+                self.next_bell_at().saturating_duration_since(self.clock.now_instant())
             };
+            let pre_bell_at = self.pre_bell_at().filter(|_| timer_active);
+            let pre_bell_sleep = pre_bell_at.map_or(Duration::ZERO, |at| {
+                at.saturating_duration_since(self.clock.now_instant())
+            });
+            let pattern_sleep = until_next_minute(self.clock.now_local());
 
             tokio::select! {
                 // Handle IPC connections
                 Ok(stream) = ipc_server.accept() => {
                     let cmd_tx = cmd_tx.clone();
-                    tokio::spawn(async move {
+                    connections.spawn(async move {
                         IpcServer::handle_connection(stream, cmd_tx).await;
                     });
                 }
 
-                // Handle commands from IPC
-                Some((command, resp_tx)) = cmd_rx.recv() => {
+                // Reap finished connection handlers
+                Some(_) = connections.join_next() => {}
+
+                // Handle commands from IPC, one at a time in arrival order
+                Some((command, peer, resp_tx)) = cmd_rx.recv() => {
+                    debug!("Handling {:?} from {}", command, peer);
                     let is_stop = matches!(command, Command::Stop);
+                    let is_subscribe = matches!(command, Command::Subscribe);
                     let response = self.handle_command(command);
+                    // A refused stop (e.g. during a commitment) keeps running
+                    let is_stop = is_stop && matches!(response, Response::Ok);
 
                     let _ = resp_tx.send(response).await;
+                    if is_subscribe {
+                        self.subscribers.push(resp_tx);
+                    }
 
                     if is_stop {
                         info!("Stop command received, shutting down");
+                        // A deliberate stop starts the next daemon afresh
+                        if let Err(e) = SavedState::default().save().await {
+                            warn!("Failed to remove saved daemon state: {}", e);
+                        }
                         break;
                     }
                 }
 
                 // Handle lock events
                 Some(event) = lock_rx.recv() => {
-                    self.handle_lock_event(event);
+                    self.handle_screen_lock(event);
+                }
+
+                // Handle idle events
+                Some(event) = async { idle_rx.as_mut()?.recv().await }, if idle_rx.is_some() => {
+                    self.handle_idle_event(event);
+                }
+
+                // Calendar busy check
+                _ = calendar_tick.tick(), if self.calendar.is_some() => {
+                    self.check_calendar();
+                }
+
+                // Presence accounting
+                _ = presence_tick.tick(), if self.config.require_presence => {
+                    self.check_presence().await;
+                }
+
+                // End-of-day summary
+                _ = summary_tick.tick(), if self.config.daily_summary_at.is_some() => {
+                    self.check_daily_summary();
+                }
+
+                // Save running time so a crash can't lose much of it
+                _ = active_flush_tick.tick() => {
+                    self.stats.save_or_warn().await;
+                }
+
+                // Weekday change for [schedule]
+                _ = schedule_tick.tick(), if self.config.schedule.is_some() => {
+                    self.check_schedule();
+                }
+
+                // Stats backup, first on startup and then daily
+                _ = backup_tick.tick(), if self.config.stats_backup_keep.is_some() => {
+                    match Stats::backup(self.config.stats_backup_keep).await {
+                        Ok(Some(path)) => info!("Stats backed up to {:?}", path),
+                        Ok(None) => {}
+                        Err(e) => warn!("Failed to back up stats: {}", e),
+                    }
+                }
+
+                // Soft warning a few seconds ahead of the bell
+                _ = sleep(pre_bell_sleep), if pre_bell_at.is_some() => {
+                    self.ring_pre_bell();
+                }
+
+                // Bell patterns, at each minute boundary
+                _ = sleep(pattern_sleep), if !self.config.patterns.is_empty() => {
+                    self.check_patterns().await;
                 }
 
                 // Dynamic timer - wakes exactly when next bell is due
-                _ = sleep(sleep_duration) => {
-                    if self.state == DaemonState::Running {
-                        self.ring_bell().await;
+                _ = sleep(sleep_duration), if timer_active => {
+                    self.check_calendar();
+                    let due = self.ring_pending
+                        || self.scheduler.should_ring(&self.config, self.clock.now_instant());
+                    if self.state == DaemonState::Running && due {
+                        // A pattern bell in the same minute takes its place
+                        self.check_patterns().await;
+                        if self.pattern_rang_this_minute() {
+                            self.give_way_to_pattern();
+                        } else {
+                            self.ring_bell().await;
+                        }
                     }
                 }
 
@@ -123,118 +558,1208 @@ impl Daemon {
                     info!("SIGINT received, shutting down");
                     break;
                 }
+                _ = sighup.recv() => {
+                    info!("SIGHUP received, reloading configuration");
+                    if let Err(e) = self.reload_config() {
+                        warn!("Failed to reload config, keeping the current one: {}", e);
+                    }
+                }
             }
         }
 
+        // Refuse commands queued behind the shutdown and connections still
+        // arriving, then give connection handlers a moment to write their
+        // replies before the runtime exits
+        cmd_rx.close();
+        while let Some((command, peer, resp_tx)) = cmd_rx.recv().await {
+            debug!("Answering {:?} from {} during shutdown", command, peer);
+            let _ = resp_tx.send(Response::ShuttingDown).await;
+        }
+        while let Ok(Ok(stream)) =
+            tokio::time::timeout(SHUTDOWN_ACCEPT_GRACE, ipc_server.accept()).await
+        {
+            // Its command can't be queued any more, so the handler refuses it
+            let cmd_tx = cmd_tx.clone();
+            connections.spawn(async move {
+                IpcServer::handle_connection(stream, cmd_tx).await;
+            });
+        }
+        drop(cmd_tx);
+        // Ends the subscribers' event streams
+        self.subscribers.clear();
+        let flush = async { while connections.join_next().await.is_some() {} };
+        if tokio::time::timeout(SHUTDOWN_FLUSH_TIMEOUT, flush).await.is_err() {
+            debug!("Abandoning idle IPC connections");
+        }
+
+        self.shutdown().await;
+
         // Clean up the lock monitor task
         lock_handle.abort();
+        if let Some(handle) = idle_handle {
+            handle.abort();
+        }
 
         info!("Daemon stopped");
         Ok(())
     }
 
+    /// Stop the bell, let background saves finish, then close the session
+    /// and write the stats one last time
+    async fn shutdown(&mut self) {
+        self.stop_playback();
+        while self.pending_saves.join_next().await.is_some() {}
+        self.account_active_time();
+        self.close_session();
+        match self.stats.save().await {
+            Ok(()) => debug!("Final stats saved"),
+            Err(e) => error!("Failed to save stats on shutdown: {}", e),
+        }
+    }
+
+    /// Run a stats save in the background, tracked so shutdown can wait
+    /// for it
+    fn spawn_save(&mut self, save: impl Future<Output = ()> + Send + 'static) {
+        // Drop the results of saves that already finished
+        while self.pending_saves.try_join_next().is_some() {}
+        self.pending_saves.spawn(save);
+    }
+
+    /// Record the current session in stats, without saving
+    fn close_session(&mut self) {
+        let session = SessionRecord {
+            started: self.session_started,
+            duration_secs: self.elapsed_since(self.started_at).as_secs(),
+            bells: self.bells_this_session,
+        };
+        self.stats.record_session(session, self.config.session_history_keep);
+    }
+
+    /// Time since `at` on the daemon's clock
+    fn elapsed_since(&self, at: Instant) -> Duration {
+        self.clock.now_instant().saturating_duration_since(at)
+    }
+
+    /// Time between bells, derived from the current config
+    fn interval_duration(&self) -> Duration {
+        self.scheduler.interval(&self.config)
+    }
+
+    /// Move to the next pomodoro phase at a bell
+    fn advance_phase(&mut self) {
+        let phase = self.scheduler.advance_phase(&self.config);
+        info!(
+            "Pomodoro: {} for {} minutes",
+            phase,
+            self.interval_duration().as_secs() / 60
+        );
+    }
+
+    /// Freeze the pomodoro countdown while the bell is paused or locked
+    fn suspend_phase(&mut self) {
+        self.scheduler.suspend_phase(&self.config, self.clock.now_instant());
+    }
+
+    /// Continue a frozen pomodoro countdown, returning whether there was one
+    fn resume_phase(&mut self) -> bool {
+        self.scheduler.resume_phase(&self.config, self.clock.now_instant())
+    }
+
+    /// When the next bell is due on the wall-clock timer
+    fn next_bell_at(&self) -> Instant {
+        self.scheduler.next_bell_at(&self.config)
+    }
+
+    /// Number of escalation steps to apply, zero unless escalation is configured
+    fn escalation_level(&self) -> u32 {
+        self.config
+            .snooze_escalation
+            .as_ref()
+            .map_or(0, |e| self.consecutive_snoozes.min(e.max_steps))
+    }
+
     fn handle_command(&mut self, command: Command) -> Response {
+        if let Some(remaining) = self.commitment_remaining() {
+            if matches!(command, Command::Stop | Command::Pause) {
+                let secs = remaining.as_secs();
+                return Response::Error(format!(
+                    "Committed session: {}:{:02} remaining",
+                    secs / 60,
+                    secs % 60
+                ));
+            }
+        }
+
+        // Read-only queries, e.g. from a shell prompt, don't count as activity
+        if !matches!(
+            command,
+            Command::Status
+                | Command::GetStats
+                | Command::GetConfig
+                | Command::GetHistory(_)
+                | Command::Subscribe
+        ) {
+            self.last_interaction = self.clock.now_instant();
+            if self.state == DaemonState::Suppressed(AUTO_PAUSE_REASON) {
+                self.state = DaemonState::Running;
+                self.restart_interval();
+                info!(state = %self.state, "Activity detected, leaving auto-pause");
+                if matches!(command, Command::Resume) {
+                    return Response::Ok;
+                }
+            }
+        }
+
         match command {
-            Command::Pause => {
-                if self.state == DaemonState::Running {
+            Command::Pause => match self.state {
+                DaemonState::Running | DaemonState::Idle | DaemonState::Suppressed(_) => {
+                    self.suspend_phase();
                     self.state = DaemonState::Paused;
-                    info!("Bell paused");
+                    self.stop_playback();
+                    self.play_event_sound(Sound::Pause);
+                    self.broadcast(BellEvent::Paused);
+                    info!(state = %self.state, "Bell paused");
                     Response::Ok
-                } else {
+                }
+                DaemonState::Locked => {
+                    // Stay paused once the screen is unlocked
+                    self.was_paused_before_lock = true;
+                    info!("Bell paused, takes effect after unlock");
+                    Response::Ok
+                }
+                DaemonState::Paused => {
                     Response::Error(format!("Cannot pause: currently {}", self.state))
                 }
-            }
-            Command::Resume => {
-                if self.state == DaemonState::Paused {
+            },
+            Command::Resume => match self.state {
+                DaemonState::Paused => {
                     self.state = DaemonState::Running;
-                    info!("Bell resumed");
+                    if !self.resume_phase() && self.config.ring_on_resume {
+                        self.ring_pending = true;
+                    }
+                    self.play_event_sound(Sound::Resume);
+                    self.broadcast(BellEvent::Resumed);
+                    info!(state = %self.state, "Bell resumed");
                     Response::Ok
-                } else {
+                }
+                DaemonState::Locked => {
+                    // Resume ringing once the screen is unlocked
+                    self.was_paused_before_lock = false;
+                    info!("Bell resumed, takes effect after unlock");
+                    Response::Ok
+                }
+                DaemonState::Running | DaemonState::Idle | DaemonState::Suppressed(_) => {
                     Response::Error(format!("Cannot resume: currently {}", self.state))
                 }
-            }
+            },
             Command::Stop => {
+                self.stop_playback();
                 info!("Stop requested");
                 Response::Ok
             }
             Command::Status => {
-                let next_bell_secs = if self.state == DaemonState::Running {
-                    let interval_secs = self.config.interval * 60;
-                    let elapsed = self.last_bell.elapsed().as_secs();
-                    Some(interval_secs.saturating_sub(elapsed))
-                } else {
-                    None
-                };
+                let now = self.clock.now_instant();
+                let remaining = self.scheduler.remaining(&self.config, now);
+                let interval = self.interval_duration();
+                let next_bell_secs = (self.state == DaemonState::Running).then(|| {
+                    until_audible(&self.config, self.clock.now_local(), remaining, interval)
+                        .as_secs()
+                });
 
                 Response::Status(StatusInfo {
                     state: self.state.to_string(),
                     next_bell_secs,
-                    interval_mins: self.config.interval,
+                    interval_mins: self.interval_duration().as_secs() / 60,
+                    interval_overridden: self.interval_override.is_some(),
+                    interval_scheduled: self.interval_scheduled(),
+                    in_quiet_hours: self.in_quiet_hours(),
+                    phase: (self.config.mode == Mode::Pomodoro)
+                        .then(|| self.scheduler.phase().to_string()),
+                    phase_remaining_secs: (self.config.mode == Mode::Pomodoro)
+                        .then(|| self.scheduler.phase_remaining(&self.config, now).as_secs()),
+                    skip_pending: self.skip_next,
+                    daily_cap_reached: self.daily_cap_reached(),
+                    profile: self.profile.clone(),
+                    modality: self.config.audio_ramp.as_ref().map(|_| {
+                        if self.ramp_silenced() { "silent" } else { "audio" }.to_string()
+                    }),
+                    next_message: self.upcoming_message().map(str::to_string),
                     total_bells_session: self.bells_this_session,
+                    committed_secs: self.commitment_remaining().map(|r| r.as_secs()),
+                    present_secs: self
+                        .config
+                        .require_presence
+                        .then_some(self.scheduler.present().as_secs()),
                 })
             }
-            Command::Ring => {
-                // Manual ring - stats recorded asynchronously via spawn
-                self.ring_bell_sync();
+            Command::Ring { volume } => {
+                if volume.is_some_and(|v| v > 100) {
+                    return Response::Error("Volume must be between 0 and 100".to_string());
+                }
+                // Manual ring: unlike scheduled bells, it goes through quiet
+                // hours unless quiet_hours_block_manual is set, since the user
+                // explicitly asked for it
+                if self.config.quiet_hours_block_manual && self.in_quiet_hours() {
+                    return Response::Error("Manual rings are blocked during quiet hours".to_string());
+                }
+                // Keep scripted rings from stacking up; scheduled bells never
+                // come through here
+                let min_gap = Duration::from_millis(self.config.min_ring_gap_ms);
+                if let Some(since) = self.last_manual_ring.map(|at| self.elapsed_since(at)) {
+                    if since < min_gap {
+                        return Response::Error(format!(
+                            "Rang {}ms ago, manual rings must be at least {}ms apart (min_ring_gap_ms)",
+                            since.as_millis(),
+                            min_gap.as_millis()
+                        ));
+                    }
+                }
+                self.last_manual_ring = Some(self.clock.now_instant());
+                // Stats recorded asynchronously via spawn
+                self.ring_bell_sync(volume);
                 Response::Ok
             }
-            Command::Reload => {
-                match Config::load() {
-                    Ok(config) => {
-                        self.config = config;
-                        info!("Configuration reloaded");
+            Command::Snooze(mins) => {
+                if mins == 0 {
+                    return Response::Error("Snooze must be at least 1 minute".to_string());
+                }
+                if self.state != DaemonState::Running {
+                    return Response::Error(format!(
+                        "Cannot snooze while {}: snooze needs the bell running",
+                        self.state
+                    ));
+                }
+
+                self.consecutive_snoozes += 1;
+                let mut delay = Duration::from_secs(mins * 60);
+                if let Some(escalation) = &self.config.snooze_escalation {
+                    // Each consecutive snooze shortens the delay, down to a minute
+                    let cut = escalation.interval_step_pct * self.escalation_level();
+                    delay = (delay * 100u32.saturating_sub(cut) / 100).max(Duration::from_secs(60));
+                }
+
+                // Replaces any earlier snooze rather than stacking on it
+                self.scheduler.snooze_until(self.clock.now_instant() + delay);
+                info!(
+                    "Bell snoozed for {}s (snooze #{} in a row)",
+                    delay.as_secs(),
+                    self.consecutive_snoozes
+                );
+                Response::Ok
+            }
+            Command::SetAway(away) => {
+                self.handle_lock_event(if away {
+                    LockEvent::Locked
+                } else {
+                    LockEvent::Unlocked
+                });
+                Response::Ok
+            }
+            Command::GetHistory(limit) => {
+                let skip = self.history.len().saturating_sub(limit);
+                Response::History(self.history.iter().skip(skip).copied().collect())
+            }
+            Command::GetStats => Response::Stats(self.stats.clone()),
+            Command::GetConfig => Response::Config(Box::new(self.config.clone())),
+            // The run loop keeps the reply channel for events
+            Command::Subscribe => Response::Ok,
+            Command::SetInterval(mins, persist) => {
+                if mins == 0 {
+                    return Response::Error("Interval must be greater than 0".to_string());
+                }
+                if persist {
+                    let mut base = self.base_config.clone();
+                    base.interval = mins;
+                    if let Err(e) = base.save() {
+                        return Response::Error(format!("Failed to save config: {}", e));
+                    }
+                    self.base_config = base;
+                }
+
+                // Held like `start --interval` so a profile or reload can't undo it
+                self.override_interval(mins);
+                self.restart_interval();
+                info!(
+                    "Interval set to {} minutes{}",
+                    mins,
+                    if persist { " and saved" } else { "" }
+                );
+                Response::Ok
+            }
+            Command::Skip => {
+                self.skip_next = true;
+                info!("Next bell will be skipped");
+                Response::Ok
+            }
+            Command::NewSession => {
+                self.close_session();
+                info!(count = self.bells_this_session, "Session ended, starting a new one");
+                self.bells_this_session = 0;
+                self.history.clear();
+                self.started_at = self.clock.now_instant();
+                self.session_started = self.clock.now_local().with_timezone(&Utc);
+                // Save in the background, as for a manual ring
+                let stats = self.stats.clone();
+                self.spawn_save(async move { stats.save_or_warn().await });
+                Response::Ok
+            }
+            Command::ResetTimer => {
+                // A fresh full interval from now, dropping any snooze
+                self.restart_interval();
+                info!("Timer reset, next bell in {} minutes", self.interval_duration().as_secs() / 60);
+                Response::Ok
+            }
+            Command::SetProfile(name) => {
+                match self.apply_config(self.base_config.clone(), Some(name)) {
+                    Ok(_) => {
+                        self.restart_interval();
+                        info!(
+                            "Switched to profile {}, next bell in {} minutes",
+                            self.profile.as_deref().unwrap_or_default(),
+                            self.interval_duration().as_secs() / 60
+                        );
                         Response::Ok
                     }
-                    Err(e) => Response::Error(format!("Failed to reload config: {}", e)),
+                    Err(e) => Response::Error(e.to_string()),
                 }
             }
+            Command::Reload => match self.reload_config() {
+                Ok(()) => Response::Ok,
+                Err(e) => Response::Error(format!("Failed to reload config: {}", e)),
+            },
+        }
+    }
+
+    /// Re-read the config file, keeping the active profile and any interval
+    /// override. On error the current config stays in place.
+    fn reload_config(&mut self) -> Result<(), ConfigError> {
+        let config = Config::load_checked(true)?;
+        let changed = self.apply_config(config, self.profile.clone())?;
+
+        // A sound device may have appeared since startup
+        let had_audio = self.output.is_available();
+        if self.output.probe(&self.config) && !had_audio && self.config.ambient_path.is_some() {
+            self.output.set_ambient(&self.config);
+        }
+
+        if changed {
+            // Start the new interval from now rather than from the last bell
+            self.restart_interval();
+            info!(
+                "Configuration reloaded, next bell in {} minutes",
+                self.interval_duration().as_secs() / 60
+            );
+        } else {
+            info!("Configuration reloaded");
+        }
+        Ok(())
+    }
+
+    /// A lock event from the lock monitor, ignored when `pause_on_lock` is off.
+    /// An unlock still goes through so a lock from before the setting changed
+    /// can't leave the bell held; `mbell away` isn't affected.
+    fn handle_screen_lock(&mut self, event: LockEvent) {
+        if matches!(event, LockEvent::Locked) && !self.config.pause_on_lock {
+            self.last_interaction = self.clock.now_instant();
+            info!("Screen locked, bell keeps ringing (pause_on_lock is off)");
+            return;
         }
+        self.handle_lock_event(event);
     }
 
     fn handle_lock_event(&mut self, event: LockEvent) {
+        self.last_interaction = self.clock.now_instant();
         match event {
             LockEvent::Locked => {
-                self.was_paused_before_lock = self.state == DaemonState::Paused;
-                if self.state == DaemonState::Running {
+                // Lock from either Running or Paused, remembering which so
+                // Pause/Resume while locked can update it before unlock
+                if self.state != DaemonState::Locked {
+                    self.suspend_phase();
+                    self.was_paused_before_lock = self.state == DaemonState::Paused;
                     self.state = DaemonState::Locked;
-                    info!("Screen locked, pausing bell");
+                    self.broadcast(BellEvent::Locked);
+                    info!(state = %self.state, "Screen locked, pausing bell");
                 }
             }
             LockEvent::Unlocked => {
                 if self.state == DaemonState::Locked {
                     if self.was_paused_before_lock {
                         self.state = DaemonState::Paused;
-                        info!("Screen unlocked, bell remains paused (was paused before lock)");
+                        self.broadcast(BellEvent::Paused);
+                        info!(state = %self.state, "Screen unlocked, bell remains paused (was paused before lock)");
                     } else {
                         self.state = DaemonState::Running;
-                        // Reset the timer so we don't immediately ring after unlock
-                        self.last_bell = Instant::now();
-                        info!("Screen unlocked, resuming bell");
+                        // Reset the timer so we don't immediately ring after
+                        // unlock, unless a pomodoro phase is picking up again
+                        // or ring_on_resume asks for a bell right away
+                        if !self.resume_phase() {
+                            self.restart_interval();
+                            self.ring_pending = self.config.ring_on_resume;
+                        }
+                        self.broadcast(BellEvent::Resumed);
+                        info!(state = %self.state, "Screen unlocked, resuming bell");
                     }
                 }
             }
         }
     }
 
+    /// Hold the bell while the user is away from the keyboard. Only a running
+    /// bell goes idle; pause, lock and suppression take precedence.
+    fn handle_idle_event(&mut self, event: IdleEvent) {
+        match event {
+            IdleEvent::Idle => {
+                if self.state == DaemonState::Running {
+                    self.suspend_phase();
+                    self.state = DaemonState::Idle;
+                    self.broadcast(BellEvent::Idle);
+                    info!(state = %self.state, "No input for a while, pausing bell");
+                }
+            }
+            IdleEvent::Active => {
+                self.last_interaction = self.clock.now_instant();
+                if self.state == DaemonState::Idle {
+                    self.state = DaemonState::Running;
+                    // Like an unlock, start a fresh interval on return
+                    if !self.resume_phase() {
+                        self.restart_interval();
+                    }
+                    self.broadcast(BellEvent::Resumed);
+                    info!(state = %self.state, "Input resumed, resuming bell");
+                }
+            }
+        }
+    }
+
+    /// Send an event to every subscriber, dropping those that disconnected.
+    /// A subscriber that isn't reading misses events rather than blocking.
+    fn broadcast(&mut self, event: BellEvent) {
+        self.subscribers.retain(|tx| match tx.try_send(Response::Event(event.clone())) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => {
+                debug!("Subscriber is falling behind, dropping {:?}", event);
+                true
+            }
+            Err(TrySendError::Closed(_)) => false,
+        });
+    }
+
+    /// Play a bell, cutting off any earlier one still sounding
+    fn start_playback(&mut self, player: &AudioPlayer) {
+        self.stop_playback();
+        self.playback = Some(self.output.play(player));
+    }
+
+    /// Play the sound for a pause, resume or session start, if `[sounds]`
+    /// asks for one
+    fn play_event_sound(&mut self, sound: Sound) {
+        if sound.enabled(&self.config) {
+            let player = AudioPlayer::for_sound(&self.config, sound);
+            self.start_playback(&player);
+        }
+    }
+
+    fn stop_playback(&mut self) {
+        if let Some(playback) = self.playback.take() {
+            playback.cancel();
+        }
+    }
+
+    fn record_history(&mut self) {
+        if self.history.len() == HISTORY_CAPACITY {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.clock.now_local().with_timezone(&Utc));
+    }
+
+    /// Count a bell toward today's total, starting over when the date changes
+    fn count_today(&mut self) {
+        self.roll_today();
+        self.bells_today += 1;
+        if self.config.max_bells_per_day > 0 && self.bells_today == self.config.max_bells_per_day {
+            info!(
+                "Daily limit of {} bells reached, no more scheduled bells today",
+                self.config.max_bells_per_day
+            );
+        }
+    }
+
+    fn roll_today(&mut self) {
+        let today = self.stats.day_of(self.clock.now_local());
+        if today != self.today {
+            self.today = today;
+            self.bells_today = 0;
+        }
+    }
+
+    /// Whether `max_bells_per_day` has been reached today
+    fn daily_cap_reached(&self) -> bool {
+        self.config.max_bells_per_day > 0
+            && self.today == self.stats.day_of(self.clock.now_local())
+            && self.bells_today >= self.config.max_bells_per_day
+    }
+
+    /// Add the running time since the last call to stats, and start timing
+    /// again if still running
+    fn account_active_time(&mut self) {
+        let now = self.clock.now_instant();
+        if let Some(since) = self.active_since.take() {
+            self.stats.add_active(now.saturating_duration_since(since));
+        }
+        self.active_since = (self.state == DaemonState::Running).then_some(now);
+    }
+
+    /// Start a fresh interval from now
+    fn restart_interval(&mut self) {
+        self.ring_pending = false;
+        self.scheduler.restart(&self.config, self.clock.now_instant());
+    }
+
+    /// Accumulate present time, restarting the count whenever the user goes
+    /// idle, and ring once a full interval of presence has built up
+    async fn check_presence(&mut self) {
+        // Cap the step so enabling the mode via reload doesn't credit stale time
+        let now = self.clock.now_instant();
+        let elapsed = now
+            .saturating_duration_since(self.last_presence_check)
+            .min(PRESENCE_CHECK_INTERVAL);
+        self.last_presence_check = now;
+
+        if self.state != DaemonState::Running {
+            return;
+        }
+
+        match self.idle_query.idle_time().await {
+            Ok(idle) if idle >= Duration::from_secs(self.config.presence_idle_secs) => {
+                if self.scheduler.reset_presence() {
+                    debug!("User away, restarting presence count");
+                }
+                return;
+            }
+            Ok(_) => IDLE_FAILURES.reset(),
+            Err(e) => {
+                if let Some(message) = IDLE_FAILURES.check(e.to_string()) {
+                    warn!("Idle query failed, counting wall-clock time: {}", message);
+                }
+            }
+        }
+
+        self.scheduler.add_presence(elapsed);
+        if self.scheduler.should_ring(&self.config, self.clock.now_instant()) {
+            self.check_calendar();
+            if self.state == DaemonState::Running {
+                self.ring_bell().await;
+            }
+        }
+    }
+
+    /// Suppress the bell while a busy calendar event is in progress
+    fn check_calendar(&mut self) {
+        let Some(calendar) = self.calendar.as_mut() else {
+            if self.state == DaemonState::Suppressed("calendar") {
+                // Calendar was removed from the config while suppressed
+                self.state = DaemonState::Running;
+            }
+            return;
+        };
+
+        let busy = calendar.is_busy(self.clock.now_local());
+        match self.state {
+            DaemonState::Running if busy => {
+                self.state = DaemonState::Suppressed("calendar");
+                info!(state = %self.state, "Calendar busy, suppressing bell");
+            }
+            DaemonState::Suppressed("calendar") if !busy => {
+                self.state = DaemonState::Running;
+                // Start a fresh interval rather than ringing as the meeting ends
+                self.restart_interval();
+                info!(state = %self.state, "Calendar free, resuming bell");
+            }
+            _ => {}
+        }
+    }
+
+    /// Log the day's practice once `daily_summary_at` has passed, at most
+    /// once a day and only within `SUMMARY_WINDOW` of the set time
+    fn check_daily_summary(&mut self) {
+        let Some(at) = self.config.daily_summary_at else {
+            return;
+        };
+        let now = self.clock.now_local().naive_local();
+        let today = now.date();
+        if self.last_summary == Some(today) {
+            return;
+        }
+
+        let due = today.and_time(at);
+        if now >= due && now - due <= SUMMARY_WINDOW {
+            self.last_summary = Some(today);
+            let summary = self.stats.daily_summary(self.stats.day_of(self.clock.now_local()));
+            info!("{}", summary);
+            if self.config.notify {
+                notify::send_async(summary);
+            }
+        }
+    }
+
+    /// Switch to the new day's `[schedule]` interval once the weekday changes.
+    /// The next bell is due that interval after the last one.
+    fn check_schedule(&mut self) {
+        let today = self.clock.now_local().weekday();
+        if today == self.schedule_day {
+            return;
+        }
+
+        self.schedule_day = today;
+        match self.apply_config(self.base_config.clone(), self.profile.clone()) {
+            Ok(true) => info!(
+                "Schedule for {}: interval of {} minutes",
+                today,
+                self.interval_duration().as_secs() / 60
+            ),
+            Ok(false) => {}
+            Err(e) => warn!("Failed to apply schedule for {}: {}", today, e),
+        }
+    }
+
+    /// Whether today's `[schedule]` entry sets the interval, rather than a
+    /// profile or an override
+    fn interval_scheduled(&self) -> bool {
+        let profile_interval = self
+            .profile
+            .as_ref()
+            .and_then(|name| self.base_config.profiles.get(name))
+            .is_some_and(|profile| profile.interval.is_some());
+        self.interval_override.is_none()
+            && !profile_interval
+            && self
+                .config
+                .schedule
+                .as_ref()
+                .is_some_and(|schedule| schedule.interval_for(self.schedule_day).is_some())
+    }
+
+    /// Ring the `[patterns]` entry for the current minute, if any, once.
+    /// Like scheduled bells, patterns stay silent in quiet hours and past the
+    /// daily cap.
+    async fn check_patterns(&mut self) {
+        let now = self.clock.now_local();
+        let minute = local_minute(now);
+        if self.pattern_minute == Some(minute) {
+            return;
+        }
+        let Some((name, pattern)) = self.config.pattern_at(now.time()) else {
+            return;
+        };
+        let (name, repeat) = (name.to_string(), pattern.repeat);
+        self.pattern_minute = Some(minute);
+        if self.state != DaemonState::Running || self.auto_pause_if_inactive() {
+            return;
+        }
+
+        self.roll_today();
+        if self.daily_cap_reached() || self.in_quiet_hours() {
+            debug!("Pattern {} won't sound now, skipping it", name);
+            self.stats.record_suppressed().await;
+            return;
+        }
+
+        debug!("Ringing pattern {}, {} strikes", name, repeat);
+        let mut player = self.bell_player();
+        player.set_repeat(repeat);
+        self.sound_bell(player).await;
+    }
+
+    /// Whether a `[patterns]` entry matched the current minute
+    fn pattern_rang_this_minute(&self) -> bool {
+        self.pattern_minute == Some(local_minute(self.clock.now_local()))
+    }
+
+    /// Let a pattern bell stand in for an interval bell due in the same
+    /// minute, moving on to the next interval without ringing again
+    fn give_way_to_pattern(&mut self) {
+        debug!("Pattern bell this minute, skipping the interval bell");
+        if self.config.mode == Mode::Pomodoro {
+            self.advance_phase();
+        }
+        self.restart_interval();
+    }
+
+    /// When to play the pre-bell for the next bell, if `pre_bell_secs` is set
+    /// and it hasn't played yet. A pre-bell that would land before the
+    /// interval began, e.g. with a very short interval, is left out.
+    fn pre_bell_at(&self) -> Option<Instant> {
+        if self.config.pre_bell_secs == 0 || self.ring_pending {
+            return None;
+        }
+        let bell_at = self.next_bell_at();
+        if self.pre_bell_for == Some(bell_at) {
+            return None;
+        }
+        bell_at
+            .checked_sub(Duration::from_secs(self.config.pre_bell_secs))
+            .filter(|&at| at >= self.scheduler.last_bell())
+    }
+
+    /// Play the quiet pre-bell, unless the bell it announces won't sound
+    fn ring_pre_bell(&mut self) {
+        let bell_at = self.next_bell_at();
+        self.pre_bell_for = Some(bell_at);
+
+        let until_bell = bell_at.saturating_duration_since(self.clock.now_instant());
+        let now = self.clock.now_local();
+        let bell_time = chrono::Duration::from_std(until_bell)
+            .map_or(now, |d| now + d)
+            .time();
+        if self.skip_next
+            || self.daily_cap_reached()
+            || self.config.in_quiet_hours(bell_time)
+            || self.ramp_silenced()
+        {
+            debug!("Next bell won't sound, skipping pre-bell");
+            return;
+        }
+
+        debug!("Ringing pre-bell, {}s ahead", until_bell.as_secs());
+        let pre_bell = self.bell_player().pre_bell();
+        self.output.play(&pre_bell);
+    }
+
+    /// Whether the local time falls inside the configured quiet hours
+    fn in_quiet_hours(&self) -> bool {
+        self.config.in_quiet_hours(self.clock.now_local().time())
+    }
+
+    /// Ring a scheduled bell, staying silent during quiet hours
     async fn ring_bell(&mut self) {
+        if self.auto_pause_if_inactive() {
+            return;
+        }
+
+        self.roll_today();
+        if self.daily_cap_reached() {
+            debug!("Daily limit reached, skipping bell");
+            self.stats.record_suppressed().await;
+            self.restart_interval();
+            return;
+        }
+
+        // The phase changes even if its bell is skipped or silenced below
+        if self.config.mode == Mode::Pomodoro {
+            self.advance_phase();
+        }
+
+        if self.skip_next {
+            // Neither counted for the session nor recorded in stats
+            self.skip_next = false;
+            self.restart_interval();
+            info!("Skipped bell as requested");
+            return;
+        }
+
+        if self.in_quiet_hours() {
+            debug!("In quiet hours, skipping bell");
+            self.stats.record_suppressed().await;
+            self.restart_interval();
+            return;
+        }
+
         debug!("Ringing bell");
-        audio::ring_async(self.config.volume);
+        let mut player = self.bell_player();
+        if self.config.mode == Mode::Pomodoro {
+            // Tell the phases apart by ear
+            player.set_repeat(match self.scheduler.phase() {
+                PomodoroPhase::Work => 1,
+                PomodoroPhase::Break => 2,
+                PomodoroPhase::LongBreak => 3,
+            });
+        }
+        if self.scheduler.is_snoozed() {
+            // A snoozed bell gets louder with each consecutive snooze,
+            // unless silent mode is on
+            if let Some(escalation) = self
+                .config
+                .snooze_escalation
+                .as_ref()
+                .filter(|_| !player.is_silent())
+            {
+                let boost = escalation.volume_step as u32 * self.escalation_level();
+                player.set_volume((self.bell_volume() as u32 + boost).min(100) as u8);
+            }
+        } else {
+            // Letting a regular bell ring clears the escalation
+            self.consecutive_snoozes = 0;
+        }
+        self.sound_bell(player).await;
+        self.restart_interval();
+    }
+
+    /// Enter auto-pause once `auto_pause_after_mins` pass without activity,
+    /// returning whether it did
+    fn auto_pause_if_inactive(&mut self) -> bool {
+        let Some(mins) = self.config.auto_pause_after_mins else {
+            return false;
+        };
+        if self.elapsed_since(self.last_interaction) < Duration::from_secs(mins * 60) {
+            return false;
+        }
+        self.state = DaemonState::Suppressed(AUTO_PAUSE_REASON);
+        info!(
+            state = %self.state,
+            "No activity for {} minutes, auto-pausing until the next command",
+            mins
+        );
+        true
+    }
+
+    /// Play a scheduled or pattern bell on `player` and count it everywhere:
+    /// session, history, today, stats and the announcement
+    async fn sound_bell(&mut self, mut player: AudioPlayer) {
+        if self.ramp_silenced() {
+            debug!("Session past audio_ramp limits, ringing silently");
+            player.set_volume(0);
+        }
+        if !player.is_silent() && !self.output.is_available() {
+            // Saved along with the bell below
+            self.stats.suppressed_bells += 1;
+        }
+        self.start_playback(&player);
         self.bells_this_session += 1;
-        self.stats.record_bell().await;
-        self.last_bell = Instant::now();
-        info!("Bell #{} this session", self.bells_this_session);
+        self.announce_count(&player);
+        self.record_history();
+        self.count_today();
+        if let Some(streak) = self.stats.record_bell(self.clock.now_local()).await {
+            celebrate_record(&self.config, &self.output, &player, streak);
+        }
+        self.announce_bell();
+    }
+
+    /// Volume for the next bell: `volume_ramp`'s step for this point in the
+    /// session, or `volume`. Silent mode wins over the ramp.
+    fn bell_volume(&self) -> u8 {
+        match &self.config.volume_ramp {
+            Some(ramp) if self.config.volume > 0 => ramp.volume_after(self.bells_this_session),
+            _ => self.config.volume,
+        }
+    }
+
+    /// Player for the next bell, at `bell_volume` with `volume_variation`
+    /// applied
+    fn bell_player(&mut self) -> AudioPlayer {
+        let mut player = AudioPlayer::from_config(&self.config);
+        player.set_volume(audio::vary_volume(
+            self.bell_volume(),
+            self.config.volume_variation,
+            &mut self.volume_rng,
+        ));
+        player
+    }
+
+    /// Player for `mbell ring`, at `volume` if given
+    fn manual_player(&mut self, volume: Option<u8>) -> AudioPlayer {
+        let mut player = self.bell_player();
+        if let Some(volume) = volume {
+            player.set_volume(volume);
+        }
+        player
+    }
+
+    /// Whether the session has passed the `audio_ramp` limits, after which
+    /// scheduled bells ring without audio
+    fn ramp_silenced(&self) -> bool {
+        let Some(ramp) = &self.config.audio_ramp else {
+            return false;
+        };
+        ramp.after_bells
+            .is_some_and(|bells| self.bells_this_session >= bells)
+            || ramp
+                .after_mins
+                .is_some_and(|mins| self.elapsed_since(self.started_at) >= Duration::from_secs(mins * 60))
+    }
+
+    /// Announce the session count once `player`'s bell has finished, unless
+    /// it's stopped first: with `announce_command` if set, otherwise with
+    /// strikes of the bowl
+    fn announce_count(&self, player: &AudioPlayer) {
+        if !self.config.announce_count || player.is_silent() {
+            return;
+        }
+        let Some(bell) = self.playback.clone() else {
+            return;
+        };
+
+        let count = self.bells_this_session;
+        let command = self
+            .config
+            .announce_command
+            .as_deref()
+            .map(|template| announce::render_command(template, count));
+        let mut strikes = player.clone();
+        strikes.set_repeat(announce::strikes(count));
+        let wait = player.play_duration().unwrap_or_default();
+        let output = self.output.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                _ = bell.cancelled() => return,
+                _ = sleep(wait) => {}
+            }
+            match command {
+                Some(command) => announce::run_async(command),
+                None => {
+                    output.play(&strikes);
+                }
+            }
+        });
+    }
+
+    /// Message the next bell will carry, if any are configured
+    fn upcoming_message(&self) -> Option<&str> {
+        let messages = &self.config.messages;
+        // The list may have shrunk on reload, so wrap rather than index directly
+        (!messages.is_empty()).then(|| messages[self.message_index % messages.len()].as_str())
+    }
+
+    /// Log a rung bell with its message and raise a notification if
+    /// enabled, advancing the message rotation
+    fn announce_bell(&mut self) {
+        let message = self.upcoming_message();
+        if self.config.notify {
+            notify::send_async(notify::render_body(
+                &self.config.notify_body,
+                self.bells_this_session,
+                message,
+            ));
+        }
+
+        let count = self.bells_this_session;
+        match message {
+            Some(message) => info!(count, "Bell #{} this session: {}", count, message),
+            None => info!(count, "Bell #{} this session", count),
+        }
+
+        let event = BellEvent::BellRang {
+            count: self.bells_this_session,
+            message: message.map(str::to_string),
+        };
+        if message.is_some() {
+            self.message_index = (self.message_index + 1) % self.config.messages.len();
+        }
+        self.broadcast(event);
+    }
+
+    /// Ring the `ring_on_start` bell, unless quiet hours or the daily cap
+    /// hold bells back. It only counts with `count_start_bell`.
+    async fn ring_start_bell(&mut self) {
+        self.roll_today();
+        if self.daily_cap_reached() || self.in_quiet_hours() {
+            debug!("Scheduled bells are held back, skipping start bell");
+            return;
+        }
+
+        debug!("Ringing start bell");
+        let player = self.bell_player();
+        self.start_playback(&player);
+        if self.config.count_start_bell {
+            self.bells_this_session += 1;
+            self.record_history();
+            self.count_today();
+            if let Some(streak) = self.stats.record_bell(self.clock.now_local()).await {
+                celebrate_record(&self.config, &self.output, &player, streak);
+            }
+        }
+    }
+
+    /// Play the opening strikes without blocking startup. The interval timer
+    /// is restarted so the first regular bell is a full interval away.
+    async fn play_opening_burst(&mut self, burst: &OpeningBurst) {
+        debug!("Playing opening burst of {} strikes", burst.count);
+        // The burst spaces out its own strikes, so each is a single one
+        let mut player = AudioPlayer::from_config(&self.config);
+        player.set_repeat(1);
+        let gap = Duration::from_secs_f32(burst.gap_secs);
+        let count = burst.count;
+        let output = self.output.clone();
+        tokio::spawn(async move {
+            for strike in 0..count {
+                if strike > 0 {
+                    sleep(gap).await;
+                }
+                output.play(&player);
+            }
+        });
+
+        if burst.count_in_stats {
+            for _ in 0..burst.count {
+                self.bells_this_session += 1;
+                self.record_history();
+                self.count_today();
+                self.stats.record_bell(self.clock.now_local()).await;
+            }
+        }
+
+        self.restart_interval();
     }
 
-    fn ring_bell_sync(&mut self) {
+    /// Ring a manual bell, at `volume` if given
+    fn ring_bell_sync(&mut self, volume: Option<u8>) {
         debug!("Ringing bell (sync)");
-        audio::ring_async(self.config.volume);
+        let player = self.manual_player(volume);
+        self.start_playback(&player);
         self.bells_this_session += 1;
-        // Spawn async stats recording to avoid blocking the command response
-        let mut stats = self.stats.clone();
-        tokio::spawn(async move {
-            stats.record_bell().await;
+        self.announce_count(&player);
+        self.record_history();
+        self.count_today();
+        // Count in memory now and save in the background to avoid blocking
+        // the command response
+        let new_record = self.stats.count_bell(self.clock.now_local());
+        let stats = self.stats.clone();
+        let config = self.config.clone();
+        let output = self.output.clone();
+        self.spawn_save(async move {
+            stats.save_or_warn().await;
+            if let Some(streak) = new_record {
+                celebrate_record(&config, &output, &player, streak);
+            }
+        });
+        self.restart_interval();
+        self.announce_bell();
+    }
+}
+
+/// `now` truncated to the minute
+fn local_minute(now: DateTime<Local>) -> NaiveDateTime {
+    let time = now.naive_local();
+    time.with_second(0).and_then(|t| t.with_nanosecond(0)).unwrap_or(time)
+}
+
+/// Time from `now` until the next minute boundary on the local clock
+fn until_next_minute(now: DateTime<Local>) -> Duration {
+    let into_minute = Duration::new(now.second() as u64, now.nanosecond() % 1_000_000_000);
+    Duration::from_secs(60).saturating_sub(into_minute)
+}
+
+/// Mark a new longest streak, if enabled in the config
+fn celebrate_record(config: &Config, output: &AudioOutput, player: &AudioPlayer, streak: u64) {
+    // A first-ever bell "sets" a one-day record, which isn't worth a fanfare
+    if !config.celebrate_records || streak < 2 {
+        return;
+    }
+
+    info!("New record: {}-day streak!", streak);
+    if config.notify {
+        notify::send_async(format!("New record: {}-day streak!", streak));
+    }
+    output.play(&player.celebratory());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::MockClock;
+
+    /// A silent daemon on a clock that only moves when the test says so
+    fn test_daemon(config: Config) -> (Daemon, MockClock) {
+        crate::testing::isolate();
+        let clock = MockClock::new(Local::now());
+        let config = Config { volume: 0, ..config };
+        (Daemon::with_clock(config, Arc::new(clock.clone())), clock)
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    enum Step {
+        Lock,
+        Unlock,
+        Pause,
+        Resume,
+    }
+
+    #[test]
+    fn lock_and_pause_combine() {
+        use DaemonState::{Locked, Paused, Running};
+        use Step::*;
+
+        let cases: &[(&str, &[(Step, DaemonState)])] = &[
+            ("lock then pause", &[(Lock, Locked), (Pause, Locked), (Unlock, Paused)]),
+            ("pause then lock", &[(Pause, Paused), (Lock, Locked), (Unlock, Paused)]),
+            ("unlock while paused", &[(Pause, Paused), (Unlock, Paused), (Resume, Running)]),
+            (
+                "resume while locked",
+                &[(Pause, Paused), (Lock, Locked), (Resume, Locked), (Unlock, Running)],
+            ),
+        ];
+
+        for (name, steps) in cases {
+            let (mut daemon, _clock) = test_daemon(Config::default());
+            for (i, (step, expected)) in steps.iter().enumerate() {
+                let response = match step {
+                    Lock => {
+                        daemon.handle_lock_event(LockEvent::Locked);
+                        Response::Ok
+                    }
+                    Unlock => {
+                        daemon.handle_lock_event(LockEvent::Unlocked);
+                        Response::Ok
+                    }
+                    Pause => daemon.handle_command(Command::Pause),
+                    Resume => daemon.handle_command(Command::Resume),
+                };
+                assert!(matches!(response, Response::Ok), "{}: {:?}", name, response);
+                assert_eq!(daemon.state, *expected, "{}: step {} ({:?})", name, i + 1, step);
+            }
+        }
+    }
+
+    #[test]
+    fn reload_reschedules_from_the_new_interval() {
+        let (mut daemon, clock) = test_daemon(Config {
+            interval: 20,
+            ..Config::default()
         });
-        self.last_bell = Instant::now();
-        info!("Bell #{} this session", self.bells_this_session);
+        let start = clock.now_instant();
+        assert_eq!(daemon.next_bell_at(), start + Duration::from_secs(20 * 60));
+
+        let path = Config::config_path().unwrap();
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, "interval = 5\nvolume = 0\n").unwrap();
+        clock.advance(Duration::from_secs(3 * 60));
+
+        let response = daemon.handle_command(Command::Reload);
+        assert!(matches!(response, Response::Ok), "{:?}", response);
+        assert_eq!(daemon.config.interval, 5);
+        assert_eq!(
+            daemon.next_bell_at(),
+            clock.now_instant() + Duration::from_secs(5 * 60)
+        );
+    }
+
+    #[test]
+    fn manual_ring_matches_the_direct_path() {
+        let mut base: Config = toml::from_str(
+            r#"
+            default_profile = "soft"
+            repeat = 3
+            repeat_gap_secs = 1.5
+            fade_in_ms = 200
+            fade_out_ms = 500
+            audio_device = "usb"
+            sound_path = "/nonexistent/bowl.ogg"
+
+            [profiles.soft]
+            gain_db = -6.0
+            "#,
+        )
+        .unwrap();
+        base.volume = 0;
+        let (mut daemon, clock) = test_daemon(base.clone());
+
+        let direct = startup_config(&base, clock.now_local().weekday());
+        for volume in [None, Some(40)] {
+            assert_eq!(
+                daemon.manual_player(volume),
+                audio::ring_player(&direct, volume),
+                "volume {:?}",
+                volume
+            );
+        }
     }
 }
@@ -0,0 +1,80 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+const CELL: i64 = 11;
+const GAP: i64 = 2;
+const WEEKS: i64 = 53;
+const LEFT_MARGIN: i64 = 28;
+const TOP_MARGIN: i64 = 16;
+
+/// Fill colours from no bells to the busiest days
+const LEVELS: [&str; 5] = ["#ebedf0", "#c6dbef", "#6baed6", "#2171b5", "#08306b"];
+
+/// Render a year of per-day bell counts ending at `today` as an SVG heatmap,
+/// one column per week and one row per weekday (Sunday first)
+pub fn render_svg(daily_counts: &BTreeMap<NaiveDate, u64>, today: NaiveDate) -> String {
+    // Start on the Sunday that makes the last column contain `today`
+    let start =
+        today - Duration::days((WEEKS - 1) * 7 + today.weekday().num_days_from_sunday() as i64);
+    let max = daily_counts
+        .range(start..=today)
+        .map(|(_, &count)| count)
+        .max()
+        .unwrap_or(0);
+
+    let width = LEFT_MARGIN + WEEKS * (CELL + GAP);
+    let height = TOP_MARGIN + 7 * (CELL + GAP);
+
+    let mut svg = String::new();
+    let _ = writeln!(
+        svg,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" font-family="sans-serif" font-size="9" fill="#767676">"##
+    );
+
+    for (row, label) in [(1, "Mon"), (3, "Wed"), (5, "Fri")] {
+        let y = TOP_MARGIN + row * (CELL + GAP) + CELL - 2;
+        let _ = writeln!(svg, r#"<text x="0" y="{y}">{label}</text>"#);
+    }
+
+    let mut date = start;
+    while date <= today {
+        let offset = (date - start).num_days();
+        let (week, weekday) = (offset / 7, offset % 7);
+        let x = LEFT_MARGIN + week * (CELL + GAP);
+        let y = TOP_MARGIN + weekday * (CELL + GAP);
+
+        if date.day() == 1 {
+            let _ = writeln!(
+                svg,
+                r#"<text x="{x}" y="{}">{}</text>"#,
+                TOP_MARGIN - 5,
+                date.format("%b")
+            );
+        }
+
+        let count = daily_counts.get(&date).copied().unwrap_or(0);
+        let _ = writeln!(
+            svg,
+            r#"<rect x="{x}" y="{y}" width="{CELL}" height="{CELL}" rx="2" fill="{}"><title>{}: {} bell{}</title></rect>"#,
+            LEVELS[level(count, max)],
+            date,
+            count,
+            if count == 1 { "" } else { "s" }
+        );
+
+        date += Duration::days(1);
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Bucket a day's count into a colour level relative to the busiest day
+fn level(count: u64, max: u64) -> usize {
+    if count == 0 || max == 0 {
+        return 0;
+    }
+    let buckets = (LEVELS.len() - 1) as u64;
+    (count * buckets).div_ceil(max) as usize
+}
@@ -0,0 +1,245 @@
+use crate::config::IdleBackend;
+use crate::logging::LogLimiter;
+use std::os::fd::{AsFd, AsRawFd};
+use std::time::Duration;
+use tokio::io::unix::AsyncFd;
+use tokio::io::Interest;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
+use wayland_client::globals::{registry_queue_init, GlobalListContents};
+use wayland_client::protocol::{wl_registry, wl_seat::WlSeat};
+use wayland_client::backend::WaylandError;
+use wayland_client::{delegate_noop, Dispatch, QueueHandle};
+use wayland_protocols::ext::idle_notify::v1::client::{
+    ext_idle_notification_v1::{self, ExtIdleNotificationV1},
+    ext_idle_notifier_v1::ExtIdleNotifierV1,
+};
+use zbus::{proxy, Connection};
+
+/// How often the idle monitor samples idle time
+const IDLE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Idle monitor query failures, e.g. no ScreenSaver service, while they persist
+static MONITOR_FAILURES: LogLimiter = LogLimiter::new(100, Duration::from_secs(3600));
+
+#[derive(Debug, Clone)]
+pub enum IdleEvent {
+    /// No input for the idle timeout
+    Idle,
+    /// Input again after being idle
+    Active,
+}
+
+#[proxy(
+    interface = "org.freedesktop.ScreenSaver",
+    default_service = "org.freedesktop.ScreenSaver",
+    default_path = "/org/freedesktop/ScreenSaver"
+)]
+trait ScreenSaver {
+    /// Milliseconds since the last user input
+    fn get_session_idle_time(&self) -> zbus::Result<u32>;
+}
+
+/// Queries how long the user has been idle via org.freedesktop.ScreenSaver
+/// on the session bus. The connection is opened lazily and reused.
+#[derive(Default)]
+pub struct IdleQuery {
+    connection: Option<Connection>,
+}
+
+impl IdleQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub async fn idle_time(&mut self) -> Result<Duration, Box<dyn std::error::Error + Send + Sync>> {
+        let connection = match &self.connection {
+            Some(c) => c.clone(),
+            None => {
+                let c = Connection::session().await?;
+                self.connection = Some(c.clone());
+                c
+            }
+        };
+
+        let proxy = ScreenSaverProxy::new(&connection).await?;
+        match proxy.get_session_idle_time().await {
+            Ok(ms) => Ok(Duration::from_millis(ms as u64)),
+            Err(e) => {
+                // Reconnect next time in case the bus went away
+                self.connection = None;
+                Err(e.into())
+            }
+        }
+    }
+}
+
+/// Handle for the idle monitor that can be used to abort its task on shutdown
+pub struct IdleMonitorHandle {
+    _task: JoinHandle<()>,
+}
+
+impl IdleMonitorHandle {
+    /// Abort the idle monitor task
+    pub fn abort(&self) {
+        self._task.abort();
+    }
+}
+
+/// Start watching for idle in a background task, sending `Idle` once there
+/// has been no input for `timeout` and `Active` when input resumes.
+///
+/// The Wayland backend is told by the compositor through ext-idle-notify;
+/// the ScreenSaver backend polls idle time. `Auto` picks Wayland in a Wayland
+/// session and falls back to polling if the compositor lacks the protocol.
+/// Failures count as active, so the bell keeps ringing without either.
+pub fn start_idle_monitor(
+    timeout: Duration,
+    backend: IdleBackend,
+) -> (mpsc::Receiver<IdleEvent>, IdleMonitorHandle) {
+    let (tx, rx) = mpsc::channel(10);
+
+    let task = tokio::spawn(async move {
+        let use_wayland = match backend {
+            IdleBackend::Auto => std::env::var_os("WAYLAND_DISPLAY").is_some(),
+            IdleBackend::Wayland => true,
+            IdleBackend::Screensaver => false,
+        };
+        if use_wayland {
+            let error = match watch_wayland(timeout, &tx).await {
+                // Only returns cleanly once the receiver is gone
+                Ok(()) => return,
+                Err(e) => e,
+            };
+            // Don't leave the bell held by an idle that will never end
+            let _ = tx.send(IdleEvent::Active).await;
+            if backend != IdleBackend::Auto {
+                warn!("Wayland idle detection failed, disabling it: {}", error);
+                return;
+            }
+            warn!(
+                "Wayland idle detection unavailable, polling org.freedesktop.ScreenSaver instead: {}",
+                error
+            );
+        }
+        poll_screensaver(timeout, tx).await;
+    });
+
+    (rx, IdleMonitorHandle { _task: task })
+}
+
+/// Poll org.freedesktop.ScreenSaver for idle time until the receiver is dropped
+async fn poll_screensaver(timeout: Duration, tx: mpsc::Sender<IdleEvent>) {
+    let mut query = IdleQuery::new();
+    let mut poll = tokio::time::interval(IDLE_POLL_INTERVAL);
+    let mut idle = false;
+    loop {
+        poll.tick().await;
+        let now_idle = match query.idle_time().await {
+            Ok(time) => {
+                MONITOR_FAILURES.reset();
+                time >= timeout
+            }
+            Err(e) => {
+                if let Some(message) = MONITOR_FAILURES.check(e.to_string()) {
+                    warn!("Idle query failed, treating user as active: {}", message);
+                }
+                false
+            }
+        };
+        if now_idle == idle {
+            continue;
+        }
+
+        idle = now_idle;
+        let event = if idle { IdleEvent::Idle } else { IdleEvent::Active };
+        if tx.send(event).await.is_err() {
+            error!("Idle monitor receiver dropped");
+            break;
+        }
+    }
+}
+
+/// Events from the Wayland connection, collected while dispatching
+#[derive(Default)]
+struct WaylandIdle {
+    events: Vec<IdleEvent>,
+}
+
+impl Dispatch<wl_registry::WlRegistry, GlobalListContents> for WaylandIdle {
+    fn event(
+        _: &mut Self,
+        _: &wl_registry::WlRegistry,
+        _: wl_registry::Event,
+        _: &GlobalListContents,
+        _: &wayland_client::Connection,
+        _: &QueueHandle<Self>,
+    ) {
+    }
+}
+
+impl Dispatch<ExtIdleNotificationV1, ()> for WaylandIdle {
+    fn event(
+        state: &mut Self,
+        _: &ExtIdleNotificationV1,
+        event: ext_idle_notification_v1::Event,
+        _: &(),
+        _: &wayland_client::Connection,
+        _: &QueueHandle<Self>,
+    ) {
+        match event {
+            ext_idle_notification_v1::Event::Idled => state.events.push(IdleEvent::Idle),
+            ext_idle_notification_v1::Event::Resumed => state.events.push(IdleEvent::Active),
+            _ => {}
+        }
+    }
+}
+
+delegate_noop!(WaylandIdle: ignore WlSeat);
+delegate_noop!(WaylandIdle: ExtIdleNotifierV1);
+
+/// Ask the compositor to report idle after `timeout` via ext-idle-notify-v1
+/// and forward its events until the receiver is dropped. Errors if there is
+/// no Wayland session, the compositor lacks the protocol, or the connection
+/// breaks.
+async fn watch_wayland(
+    timeout: Duration,
+    tx: &mpsc::Sender<IdleEvent>,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let conn = wayland_client::Connection::connect_to_env()?;
+    let (globals, mut queue) = registry_queue_init::<WaylandIdle>(&conn)?;
+    let qh = queue.handle();
+    let seat: WlSeat = globals.bind(&qh, 1..=1, ())?;
+    let notifier: ExtIdleNotifierV1 = globals.bind(&qh, 1..=1, ())?;
+    let timeout_ms = u32::try_from(timeout.as_millis()).unwrap_or(u32::MAX);
+    let _notification = notifier.get_idle_notification(timeout_ms, &seat, &qh, ());
+    info!("Watching for idle through Wayland ext-idle-notify");
+
+    let fd = AsyncFd::with_interest(conn.as_fd().as_raw_fd(), Interest::READABLE)?;
+    let mut state = WaylandIdle::default();
+    loop {
+        queue.dispatch_pending(&mut state)?;
+        for event in state.events.drain(..) {
+            debug!("Wayland idle event: {:?}", event);
+            if tx.send(event).await.is_err() {
+                error!("Idle monitor receiver dropped");
+                return Ok(());
+            }
+        }
+        queue.flush()?;
+
+        // Events were queued by another reader in the meantime; dispatch them
+        let Some(guard) = queue.prepare_read() else {
+            continue;
+        };
+        let mut ready = fd.readable().await?;
+        match guard.read() {
+            Ok(_) => {}
+            Err(WaylandError::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                ready.clear_ready();
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
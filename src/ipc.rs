@@ -1,13 +1,36 @@
+use crate::config::{Config, SocketKind};
+use crate::stats::Stats;
+use chrono::{DateTime, Utc};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, Write};
+use std::os::unix::fs::{MetadataExt, OpenOptionsExt, PermissionsExt};
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{
+    SocketAddr as StdSocketAddr, UnixListener as StdUnixListener, UnixStream as StdUnixStream,
+};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::sync::OnceLock;
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::{UnixListener, UnixStream};
 use tokio::sync::mpsc;
-use tracing::{debug, error, info};
+use tokio::time::sleep;
+use tracing::{debug, error, info, warn};
 
-static SOCKET_PATH: OnceLock<PathBuf> = OnceLock::new();
+/// The socket file and where its directory came from
+static SOCKET_PATH: OnceLock<(PathBuf, &'static str)> = OnceLock::new();
+static SOCKET_ADDRESS: OnceLock<SocketAddress> = OnceLock::new();
+
+/// Events buffered per subscriber before new ones are dropped
+pub const SUBSCRIBER_BUFFER: usize = 32;
+
+/// First and longest delays between attempts in `send_command_retry`
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(1);
 
 #[derive(Error, Debug)]
 pub enum IpcError {
@@ -19,6 +42,8 @@ pub enum IpcError {
     DaemonNotRunning,
     #[error("Connection failed: {0}")]
     ConnectionFailed(String),
+    #[error("Daemon is already running (pid {0})")]
+    AlreadyRunning(i32),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,8 +53,36 @@ pub enum Command {
     Resume,
     Stop,
     Status,
-    Ring,
+    /// Ring now, at this volume instead of the configured one if set
+    Ring {
+        #[serde(default)]
+        volume: Option<u8>,
+    },
     Reload,
+    GetStats,
+    /// Delay the next bell by this many minutes from now
+    Snooze(u64),
+    /// Manually report the user as away (like a screen lock) or back
+    SetAway(bool),
+    /// Times of up to this many recent bells this session
+    GetHistory(usize),
+    /// Switch to the named profile from the config
+    SetProfile(String),
+    /// Start a full interval from now without ringing
+    ResetTimer,
+    /// Let the next scheduled bell pass without ringing
+    Skip,
+    /// Record the current session in stats and start a new one
+    NewSession,
+    /// The config in effect, with the profile, schedule and any runtime
+    /// changes applied
+    GetConfig,
+    /// Set the interval in minutes from now, also saving it to the config
+    /// file if the flag is set
+    SetInterval(u64, bool),
+    /// Keep the connection open and stream a `Response::Event` line for each
+    /// bell and state change, until the client disconnects
+    Subscribe,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,60 +90,445 @@ pub enum Command {
 pub enum Response {
     Ok,
     Status(StatusInfo),
+    Stats(Stats),
+    Config(Box<Config>),
+    /// Recent bell times, oldest first
+    History(Vec<DateTime<Utc>>),
+    /// Something happened, sent to `Subscribe` connections
+    Event(BellEvent),
     Error(String),
+    /// The command arrived once the daemon had begun shutting down,
+    /// including a `Stop` that lost the race to another one
+    ShuttingDown,
+}
+
+/// Bell and state change notifications for subscribed clients
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", content = "data")]
+pub enum BellEvent {
+    /// A bell rang, with its number this session and its message, if any
+    BellRang { count: u64, message: Option<String> },
+    Paused,
+    Resumed,
+    Locked,
+    /// No input for `idle_timeout_secs`
+    Idle,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StatusInfo {
     pub state: String,
+    /// Time until the next audible bell, skipping any that fall in quiet hours
     pub next_bell_secs: Option<u64>,
     pub interval_mins: u64,
+    /// Whether the interval was overridden with `mbell start --interval`
+    #[serde(default)]
+    pub interval_overridden: bool,
+    /// Whether the interval comes from today's `[schedule]` entry
+    #[serde(default)]
+    pub interval_scheduled: bool,
+    /// Current pomodoro phase, in pomodoro mode
+    #[serde(default)]
+    pub phase: Option<String>,
+    /// Time left in the pomodoro phase, frozen while paused or locked
+    #[serde(default)]
+    pub phase_remaining_secs: Option<u64>,
+    /// Whether scheduled bells are currently silenced by quiet hours
+    #[serde(default)]
+    pub in_quiet_hours: bool,
+    /// Whether the next scheduled bell will be skipped
+    #[serde(default)]
+    pub skip_pending: bool,
+    /// Whether `max_bells_per_day` has been reached, holding scheduled bells
+    /// until tomorrow
+    #[serde(default)]
+    pub daily_cap_reached: bool,
+    /// Active profile, if any
+    #[serde(default)]
+    pub profile: Option<String>,
+    /// How scheduled bells ring right now ("audio" or "silent"), when
+    /// `audio_ramp` is configured
+    #[serde(default)]
+    pub modality: Option<String>,
+    /// Message the next bell will carry, from the `messages` rotation
+    #[serde(default)]
+    pub next_message: Option<String>,
     pub total_bells_session: u64,
+    /// Present time accumulated toward the next bell, when `require_presence` is on
+    #[serde(default)]
+    pub present_secs: Option<u64>,
+    /// Time left in a commitment session, during which stop and pause are refused
+    #[serde(default)]
+    pub committed_secs: Option<u64>,
 }
 
+/// Identity of the process on the other end of an IPC connection
+#[derive(Debug, Clone, Copy)]
+pub struct Peer {
+    pub pid: Option<i32>,
+    pub uid: Option<u32>,
+}
+
+impl std::fmt::Display for Peer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.pid, self.uid) {
+            (Some(pid), Some(uid)) => write!(f, "pid {} (uid {})", pid, uid),
+            (None, Some(uid)) => write!(f, "unknown pid (uid {})", uid),
+            _ => write!(f, "unknown peer"),
+        }
+    }
+}
+
+/// A command from a client, with who sent it and where to send the reply
+pub type CommandRequest = (Command, Peer, mpsc::Sender<Response>);
+
 pub fn socket_path() -> &'static PathBuf {
-    SOCKET_PATH.get_or_init(|| {
-        let runtime_dir = std::env::var("XDG_RUNTIME_DIR")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| {
-                let uid = unsafe { libc::getuid() };
-                PathBuf::from(format!("/run/user/{}", uid))
-            });
-        runtime_dir.join("mbell.sock")
-    })
+    &resolved_socket_path().0
+}
+
+fn resolved_socket_path() -> &'static (PathBuf, &'static str) {
+    SOCKET_PATH.get_or_init(|| runtime_socket_path(std::env::var_os("XDG_RUNTIME_DIR")))
+}
+
+/// The socket in `runtime_dir`, else in the runtime dir systemd-logind
+/// creates for each user, else in the temp dir for minimal setups with
+/// neither. Client and daemon resolve it the same way, so they agree.
+fn runtime_socket_path(runtime_dir: Option<OsString>) -> (PathBuf, &'static str) {
+    let uid = unsafe { libc::getuid() };
+    if let Some(dir) = runtime_dir.map(PathBuf::from).filter(|dir| dir.is_dir()) {
+        return (dir.join("mbell.sock"), "XDG_RUNTIME_DIR");
+    }
+
+    let user_dir = PathBuf::from(format!("/run/user/{}", uid));
+    if cfg!(target_os = "linux") && user_dir.is_dir() {
+        return (user_dir.join("mbell.sock"), "/run/user");
+    }
+
+    // The temp dir is shared, so the uid keeps users apart
+    (
+        std::env::temp_dir().join(format!("mbell-{}.sock", uid)),
+        "the temp dir, as there is no runtime dir",
+    )
+}
+
+/// Where the daemon listens and the CLI connects
+#[derive(Debug, Clone)]
+pub enum SocketAddress {
+    /// A socket file, removed on shutdown
+    File(PathBuf),
+    /// A name in Linux's abstract namespace. It needs no writable directory
+    /// and vanishes with the daemon, but has no file permissions to guard it.
+    Abstract(String),
+}
+
+impl std::fmt::Display for SocketAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SocketAddress::File(path) => write!(f, "{}", path.display()),
+            SocketAddress::Abstract(name) => write!(f, "@{}", name),
+        }
+    }
+}
+
+/// Choose the socket from the config's `socket` before any IPC happens.
+/// `MBELL_SOCKET` ("file" or "abstract") takes precedence, and without a
+/// call the socket is a file.
+pub fn init_socket(kind: SocketKind) {
+    let _ = SOCKET_ADDRESS.set(resolve_socket(kind));
+}
+
+pub fn socket_address() -> &'static SocketAddress {
+    SOCKET_ADDRESS.get_or_init(|| resolve_socket(SocketKind::File))
+}
+
+fn resolve_socket(kind: SocketKind) -> SocketAddress {
+    let kind = match std::env::var("MBELL_SOCKET").as_deref() {
+        Ok("file") => SocketKind::File,
+        Ok("abstract") => SocketKind::Abstract,
+        _ => kind,
+    };
+    match kind {
+        // The namespace is shared by all users, so the uid keeps them apart
+        SocketKind::Abstract if cfg!(target_os = "linux") => {
+            SocketAddress::Abstract(format!("mbell-{}", unsafe { libc::getuid() }))
+        }
+        _ => SocketAddress::File(socket_path().clone()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn abstract_addr(name: &str) -> std::io::Result<StdSocketAddr> {
+    use std::os::linux::net::SocketAddrExt;
+    StdSocketAddr::from_abstract_name(name)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn abstract_addr(_name: &str) -> std::io::Result<StdSocketAddr> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "abstract sockets are only supported on Linux",
+    ))
+}
+
+/// Blocking connect, for quick liveness checks outside the async client
+fn connect_blocking() -> std::io::Result<StdUnixStream> {
+    match socket_address() {
+        SocketAddress::File(path) => StdUnixStream::connect(path),
+        SocketAddress::Abstract(name) => StdUnixStream::connect_addr(&abstract_addr(name)?),
+    }
+}
+
+/// Pid of the process on the other end of `stream`, from SO_PEERCRED
+#[cfg(target_os = "linux")]
+fn peer_pid(stream: &StdUnixStream) -> Option<i32> {
+    let mut cred = libc::ucred {
+        pid: 0,
+        uid: 0,
+        gid: 0,
+    };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    (ret == 0 && cred.pid > 0).then_some(cred.pid)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn peer_pid(_stream: &StdUnixStream) -> Option<i32> {
+    None
+}
+
+/// Lockfile next to the socket, holding the daemon's pid
+pub fn pid_path() -> PathBuf {
+    socket_path().with_extension("pid")
+}
+
+/// Exclusive `flock` on the pid file, held for the daemon's lifetime. The
+/// kernel drops the lock when the process dies, so a crash can't leave a
+/// stale lock behind the way it leaves a stale socket
+pub struct PidLock {
+    file: File,
+}
+
+impl PidLock {
+    /// Take the lock and record our pid, or fail if another daemon holds it
+    pub fn acquire() -> Result<Self, IpcError> {
+        let path = pid_path();
+        let mut file = loop {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .mode(0o600)
+                .open(&path)?;
+
+            if let Err(e) = try_flock(&file, libc::LOCK_EX) {
+                return match e.kind() {
+                    std::io::ErrorKind::WouldBlock => {
+                        Err(IpcError::AlreadyRunning(read_pid(&mut file).unwrap_or(0)))
+                    }
+                    _ => Err(e.into()),
+                };
+            }
+
+            // A daemon shutting down removes the file before unlocking it, so
+            // the lock only counts if the file is still the one at the path
+            if is_same_file(&file, &path) {
+                break file;
+            }
+            debug!("Pid file {:?} was replaced while locking, retrying", path);
+        };
+
+        file.set_len(0)?;
+        file.rewind()?;
+        writeln!(file, "{}", std::process::id())?;
+        debug!("Holding pid lock {:?}", path);
+        Ok(Self { file })
+    }
+}
+
+impl Drop for PidLock {
+    fn drop(&mut self) {
+        // Remove while still locked so a new daemon can't lose its file to us
+        let _ = std::fs::remove_file(pid_path());
+        let _ = self.file.set_len(0);
+    }
+}
+
+fn is_same_file(file: &File, path: &Path) -> bool {
+    match (file.metadata(), std::fs::metadata(path)) {
+        (Ok(open), Ok(current)) => open.dev() == current.dev() && open.ino() == current.ino(),
+        _ => false,
+    }
+}
+
+fn try_flock(file: &File, operation: libc::c_int) -> std::io::Result<()> {
+    match unsafe { libc::flock(file.as_raw_fd(), operation | libc::LOCK_NB) } {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+fn read_pid(file: &mut File) -> Option<i32> {
+    let mut contents = String::new();
+    file.rewind().ok()?;
+    file.read_to_string(&mut contents).ok()?;
+    contents.trim().parse().ok()
+}
+
+fn process_alive(pid: i32) -> bool {
+    // Signal 0 only checks the pid exists; EPERM means it does but isn't ours
+    pid > 0
+        && (unsafe { libc::kill(pid, 0) } == 0
+            || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM))
+}
+
+/// Pid of the running daemon, or None if there isn't one. A pid file whose
+/// lock is free was left by a daemon that died without cleaning up; an
+/// abstract socket has no file to go stale, so it just takes a connect.
+pub fn running_daemon_pid() -> Option<i32> {
+    if let SocketAddress::Abstract(_) = socket_address() {
+        let stream = connect_blocking().ok()?;
+        return Some(peer_pid(&stream).unwrap_or(0));
+    }
+
+    let mut file = File::open(pid_path()).ok()?;
+    let pid = read_pid(&mut file);
+    match try_flock(&file, libc::LOCK_SH) {
+        // Nobody holds the lock; closing the file releases our probe
+        Ok(()) => None,
+        Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => Some(pid.unwrap_or(0)),
+        // Locking unsupported here (e.g. some network filesystems), so fall
+        // back to whether the recorded pid is still alive
+        Err(_) => pid.filter(|&pid| process_alive(pid)),
+    }
+}
+
+/// Remove the socket left by a daemon that died without cleaning up.
+/// Returns whether there was one. Only call this while holding `PidLock`,
+/// or it may remove a live daemon's socket.
+pub fn remove_stale_socket(_lock: &PidLock) -> bool {
+    if let SocketAddress::Abstract(_) = socket_address() {
+        return false;
+    }
+
+    match std::fs::remove_file(socket_path()) {
+        Ok(()) => {
+            debug!("Removed stale {:?}", socket_path());
+            true
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => false,
+        Err(e) => {
+            debug!("Failed to remove stale {:?}: {}", socket_path(), e);
+            false
+        }
+    }
 }
 
 /// Server side - runs in the daemon
 pub struct IpcServer {
     listener: UnixListener,
+    /// Only uid allowed to connect, for abstract sockets, which have no
+    /// permissions of their own
+    owner_uid: Option<u32>,
 }
 
 impl IpcServer {
-    pub async fn new() -> Result<Self, IpcError> {
-        let path = socket_path();
-
-        // Remove existing socket, ignoring NotFound error (avoids TOCTOU race)
-        match std::fs::remove_file(path) {
-            Ok(()) => {}
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
-            Err(e) => return Err(e.into()),
-        }
+    /// Bind the socket and restrict it to `mode`, e.g. 0o600 for owner only
+    pub async fn new(mode: u32) -> Result<Self, IpcError> {
+        match socket_address() {
+            SocketAddress::File(path) => {
+                // Remove existing socket, ignoring NotFound error (avoids TOCTOU race)
+                match std::fs::remove_file(path) {
+                    Ok(()) => {}
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                    Err(e) => return Err(e.into()),
+                }
 
-        let listener = UnixListener::bind(path)?;
-        info!("IPC server listening on {:?}", path);
+                // Bind owner-only so the socket is never reachable with the
+                // umask's looser permissions, then widen it if configured
+                let listener = {
+                    let umask = unsafe { libc::umask(0o177) };
+                    let bound = UnixListener::bind(path);
+                    unsafe { libc::umask(umask) };
+                    bound?
+                };
+                if mode != 0o600 {
+                    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))?;
+                }
+                info!(
+                    "IPC server listening on {:?} (mode {:o}, in {})",
+                    path,
+                    mode,
+                    resolved_socket_path().1
+                );
+
+                Ok(Self {
+                    listener,
+                    owner_uid: None,
+                })
+            }
+            SocketAddress::Abstract(name) => {
+                // Binding is exclusive, so a taken name means a live daemon
+                let listener = match StdUnixListener::bind_addr(&abstract_addr(name)?) {
+                    Err(e) if e.kind() == std::io::ErrorKind::AddrInUse => {
+                        return Err(IpcError::AlreadyRunning(running_daemon_pid().unwrap_or(0)));
+                    }
+                    result => result?,
+                };
+                listener.set_nonblocking(true)?;
+                let listener = UnixListener::from_std(listener)?;
+                let uid = unsafe { libc::getuid() };
+                info!("IPC server listening on @{} (uid {} only)", name, uid);
 
-        Ok(Self { listener })
+                Ok(Self {
+                    listener,
+                    owner_uid: Some(uid),
+                })
+            }
+        }
     }
 
     pub async fn accept(&self) -> Result<UnixStream, IpcError> {
-        let (stream, _) = self.listener.accept().await?;
-        Ok(stream)
+        loop {
+            let (stream, _) = self.listener.accept().await?;
+            let Some(owner) = self.owner_uid else {
+                return Ok(stream);
+            };
+            match stream.peer_cred() {
+                Ok(cred) if cred.uid() == owner => return Ok(stream),
+                Ok(cred) => warn!("Refusing IPC connection from uid {}", cred.uid()),
+                Err(e) => warn!("Refusing IPC connection without credentials: {}", e),
+            }
+        }
     }
 
     pub async fn handle_connection(
         stream: UnixStream,
-        cmd_tx: mpsc::Sender<(Command, mpsc::Sender<Response>)>,
+        cmd_tx: mpsc::Sender<CommandRequest>,
     ) {
+        // SO_PEERCRED, captured before the stream is split
+        let peer = match stream.peer_cred() {
+            Ok(cred) => Peer {
+                pid: cred.pid(),
+                uid: Some(cred.uid()),
+            },
+            Err(e) => {
+                debug!("Failed to read peer credentials: {}", e);
+                Peer {
+                    pid: None,
+                    uid: None,
+                }
+            }
+        };
+
         let (reader, mut writer) = stream.into_split();
         let mut reader = BufReader::new(reader);
         let mut line = String::new();
@@ -116,25 +554,53 @@ impl IpcServer {
             }
         };
 
-        debug!("Received command: {:?}", command);
+        debug!("Received command {:?} from {}", command, peer);
+        let subscribe = matches!(command, Command::Subscribe);
 
-        // Create response channel
-        let (resp_tx, mut resp_rx) = mpsc::channel(1);
+        // Create response channel; subscribers keep it for their events
+        let (resp_tx, mut resp_rx) = mpsc::channel(if subscribe { SUBSCRIBER_BUFFER } else { 1 });
 
-        // Send command to daemon
-        if cmd_tx.send((command, resp_tx)).await.is_err() {
-            let response = Response::Error("Daemon not responding".to_string());
+        // Send command to daemon; this only fails once it has stopped taking commands
+        let sent = cmd_tx.send((command, peer, resp_tx)).await;
+        if sent.is_err() {
+            let response = Response::ShuttingDown;
             if let Err(e) = write_json_response(&mut writer, &response).await {
                 error!("Failed to send error response: {}", e);
             }
             return;
         }
 
-        // Wait for response
-        if let Some(response) = resp_rx.recv().await {
-            if let Err(e) = write_json_response(&mut writer, &response).await {
-                error!("Failed to send response: {}", e);
+        // Wait for response; the daemon only drops the sender when it is going away
+        let response = resp_rx
+            .recv()
+            .await
+            .unwrap_or(Response::ShuttingDown);
+        if let Err(e) = write_json_response(&mut writer, &response).await {
+            error!("Failed to send response: {}", e);
+            return;
+        }
+
+        if subscribe && matches!(response, Response::Ok) {
+            debug!("{} subscribed to events", peer);
+            loop {
+                tokio::select! {
+                    event = resp_rx.recv() => {
+                        // The daemon drops its sender when shutting down
+                        let Some(event) = event else { break };
+                        if write_json_response(&mut writer, &event).await.is_err() {
+                            break;
+                        }
+                    }
+                    // Anything but more input (EOF or an error) means the client is gone
+                    read = reader.read_line(&mut line) => {
+                        if !matches!(read, Ok(n) if n > 0) {
+                            break;
+                        }
+                        line.clear();
+                    }
+                }
             }
+            debug!("{} unsubscribed", peer);
         }
     }
 }
@@ -152,7 +618,9 @@ async fn write_json_response<W: tokio::io::AsyncWriteExt + Unpin>(
 impl Drop for IpcServer {
     fn drop(&mut self) {
         // Remove socket, ignoring errors (avoids TOCTOU race)
-        let _ = std::fs::remove_file(socket_path());
+        if let SocketAddress::File(path) = socket_address() {
+            let _ = std::fs::remove_file(path);
+        }
     }
 }
 
@@ -161,15 +629,7 @@ pub struct IpcClient;
 
 impl IpcClient {
     pub async fn send_command(command: Command) -> Result<Response, IpcError> {
-        let path = socket_path();
-
-        if !path.exists() {
-            return Err(IpcError::DaemonNotRunning);
-        }
-
-        let stream = UnixStream::connect(&path)
-            .await
-            .map_err(|e| IpcError::ConnectionFailed(e.to_string()))?;
+        let stream = Self::connect().await?;
 
         let (reader, mut writer) = stream.into_split();
         let mut reader = BufReader::new(reader);
@@ -186,7 +646,85 @@ impl IpcClient {
         Ok(response)
     }
 
+    /// Like `send_command`, but while the daemon isn't accepting connections
+    /// yet (e.g. just after `mbell start -d`), retry with exponential backoff
+    /// and jitter for up to `timeout`
+    pub async fn send_command_retry(
+        command: Command,
+        timeout: Duration,
+    ) -> Result<Response, IpcError> {
+        let deadline = Instant::now() + timeout;
+        let mut delay = RETRY_BASE_DELAY;
+        loop {
+            match Self::send_command(command.clone()).await {
+                Err(IpcError::DaemonNotRunning | IpcError::ConnectionFailed(_))
+                    if Instant::now() < deadline =>
+                {
+                    // Jitter keeps concurrent clients from retrying in lockstep
+                    let jittered = delay.mul_f64(rand::rng().random_range(0.5..1.5));
+                    sleep(jittered.min(deadline.saturating_duration_since(Instant::now()))).await;
+                    delay = (delay * 2).min(RETRY_MAX_DELAY);
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn connect() -> Result<UnixStream, IpcError> {
+        match socket_address() {
+            SocketAddress::File(path) => {
+                if !path.exists() {
+                    return Err(IpcError::DaemonNotRunning);
+                }
+                UnixStream::connect(path)
+                    .await
+                    .map_err(|e| IpcError::ConnectionFailed(e.to_string()))
+            }
+            SocketAddress::Abstract(name) => {
+                // Connecting to a local socket doesn't block, so the std call is fine
+                let stream = match StdUnixStream::connect_addr(&abstract_addr(name)?) {
+                    Err(e) if e.kind() == std::io::ErrorKind::ConnectionRefused => {
+                        return Err(IpcError::DaemonNotRunning);
+                    }
+                    result => result.map_err(|e| IpcError::ConnectionFailed(e.to_string()))?,
+                };
+                stream.set_nonblocking(true)?;
+                Ok(UnixStream::from_std(stream)?)
+            }
+        }
+    }
+
+    /// Whether a daemon is accepting connections. A socket file left behind
+    /// by a crash refuses them, so this connects rather than checking the path.
     pub fn is_daemon_running() -> bool {
-        socket_path().exists()
+        connect_blocking().is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fallback_socket_dir_is_writable() {
+        let (path, _) = runtime_socket_path(None);
+        let dir = path.parent().expect("socket path has a parent");
+        assert!(dir.is_dir(), "{:?} doesn't exist", dir);
+
+        let probe = dir.join(format!("mbell-test-{}", std::process::id()));
+        File::create(&probe).expect("socket dir is writable");
+        std::fs::remove_file(probe).unwrap();
+    }
+
+    #[tokio::test]
+    async fn server_socket_gets_the_configured_mode() {
+        let runtime_dir = crate::testing::isolate().join("run");
+
+        assert_eq!(socket_path(), &runtime_dir.join("mbell.sock"));
+        for wanted in [0o600, 0o660] {
+            let _server = IpcServer::new(wanted).await.unwrap();
+            let mode = std::fs::metadata(socket_path()).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, wanted);
+        }
     }
 }
@@ -1,7 +1,22 @@
+// The IPC socket, signal handling and daemonizing all rely on Unix APIs
+#[cfg(not(unix))]
+compile_error!("mbell only supports Unix platforms");
+
+pub mod announce;
 pub mod audio;
+pub mod calendar;
+pub mod clock;
 pub mod config;
 pub mod daemon;
+pub mod heatmap;
+pub mod idle;
 pub mod ipc;
 pub mod lock;
 pub mod logging;
+pub mod notify;
+pub mod schedule;
+pub mod state;
 pub mod stats;
+
+#[cfg(test)]
+mod testing;
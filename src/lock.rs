@@ -1,15 +1,29 @@
+use crate::config::LockBackend;
+use futures_util::future::BoxFuture;
 use futures_util::StreamExt;
+use std::time::Duration;
 use tokio::sync::mpsc;
 use tokio::task::JoinHandle;
-use tracing::{debug, error, info, warn};
+use tokio::time::sleep;
+use tracing::{error, info, warn};
+#[cfg(target_os = "linux")]
+use zbus::zvariant::OwnedObjectPath;
 use zbus::{proxy, Connection};
 
+/// Wait before the first attempt to reconnect a lost lock backend, doubled
+/// after each failed attempt up to `RECONNECT_MAX_DELAY`
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
 #[derive(Debug, Clone)]
 pub enum LockEvent {
     Locked,
     Unlocked,
 }
 
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+#[cfg(target_os = "linux")]
 #[proxy(
     interface = "org.freedesktop.login1.Session",
     default_service = "org.freedesktop.login1"
@@ -25,98 +39,299 @@ trait Session {
     fn locked_hint(&self) -> zbus::Result<bool>;
 }
 
+#[cfg(target_os = "linux")]
+#[proxy(
+    interface = "org.freedesktop.login1.Manager",
+    default_service = "org.freedesktop.login1",
+    default_path = "/org/freedesktop/login1"
+)]
+trait LoginManager {
+    fn get_session(&self, session_id: &str) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(name = "GetSessionByPID")]
+    fn get_session_by_pid(&self, pid: u32) -> zbus::Result<OwnedObjectPath>;
+
+    fn get_user(&self, uid: u32) -> zbus::Result<OwnedObjectPath>;
+
+    #[zbus(signal)]
+    fn session_new(&self, session_id: String, object_path: OwnedObjectPath) -> zbus::Result<()>;
+
+    #[zbus(signal)]
+    fn session_removed(&self, session_id: String, object_path: OwnedObjectPath)
+        -> zbus::Result<()>;
+}
+
+#[cfg(target_os = "linux")]
+#[proxy(
+    interface = "org.freedesktop.login1.User",
+    default_service = "org.freedesktop.login1"
+)]
+trait LoginUser {
+    /// The user's graphical session, or "/" when there is none
+    #[zbus(property)]
+    fn display(&self) -> zbus::Result<(String, OwnedObjectPath)>;
+}
+
+#[proxy(
+    interface = "org.freedesktop.ScreenSaver",
+    default_service = "org.freedesktop.ScreenSaver",
+    default_path = "/org/freedesktop/ScreenSaver"
+)]
+trait ScreenSaverLock {
+    #[zbus(signal)]
+    fn active_changed(&self, active: bool) -> zbus::Result<()>;
+
+    fn get_active(&self) -> zbus::Result<bool>;
+}
+
 pub struct LockMonitor {
     tx: mpsc::Sender<LockEvent>,
+    backends: Vec<LockBackend>,
 }
 
 impl LockMonitor {
-    pub fn new(tx: mpsc::Sender<LockEvent>) -> Self {
-        Self { tx }
+    pub fn new(tx: mpsc::Sender<LockEvent>, backends: Vec<LockBackend>) -> Self {
+        Self { tx, backends }
     }
 
-    pub async fn run(self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let connection = Connection::system().await?;
+    /// Try each configured backend in order and monitor with the first one
+    /// that can be set up, reconnecting to it whenever its bus connection
+    /// drops
+    pub async fn run(self) -> Result<(), BoxError> {
+        for &backend in &self.backends {
+            if backend == LockBackend::Manual {
+                info!("Lock detection: manual only (mbell away)");
+                return Ok(());
+            }
 
-        // Get the current session path
-        let session_path = get_session_path(&connection).await?;
-        debug!("Monitoring session: {}", session_path);
+            match self.subscribe(backend, false).await {
+                Ok(monitor) => {
+                    info!("Lock detection: using {} backend", backend);
+                    monitor.await;
+                    self.reconnect(backend).await;
+                    return Ok(());
+                }
+                Err(e) => warn!("{} lock backend unavailable: {}", backend, e),
+            }
+        }
+
+        Err("no lock detection backend available".into())
+    }
 
-        let proxy = SessionProxy::builder(&connection)
-            .path(session_path.as_str())?
-            .build()
-            .await?;
+    /// Connect to `backend` and report its current state, then return a
+    /// future forwarding its signals. With `resync`, an unlocked state is
+    /// reported too, in case an unlock was missed while disconnected.
+    async fn subscribe(
+        &self,
+        backend: LockBackend,
+        resync: bool,
+    ) -> Result<BoxFuture<'static, ()>, BoxError> {
+        match backend {
+            LockBackend::Logind => subscribe_logind(self.tx.clone(), resync).await,
+            LockBackend::Screensaver => subscribe_screensaver(self.tx.clone(), resync).await,
+            LockBackend::Manual => Err("manual lock detection has no signals".into()),
+        }
+    }
+
+    /// Re-subscribe to `backend` each time its signal stream ends, e.g. after
+    /// suspend or a D-Bus restart, backing off while attempts fail. Returns
+    /// once the daemon stops listening.
+    async fn reconnect(&self, backend: LockBackend) {
+        let mut delay = RECONNECT_INITIAL_DELAY;
+        let mut attempt = 0;
+        while !self.tx.is_closed() {
+            if attempt == 0 {
+                warn!("{} lock signal stream ended, reconnecting", backend);
+            }
+            sleep(delay).await;
+            attempt += 1;
+            info!("Reconnecting to {} lock backend (attempt {})", backend, attempt);
 
-        // Check initial lock state
-        match proxy.locked_hint().await {
-            Ok(locked) => {
-                if locked {
-                    info!("Session is currently locked");
-                    let _ = self.tx.send(LockEvent::Locked).await;
+            match self.subscribe(backend, true).await {
+                Ok(monitor) => {
+                    info!("Lock detection: reconnected to {} backend", backend);
+                    delay = RECONNECT_INITIAL_DELAY;
+                    attempt = 0;
+                    monitor.await;
+                }
+                Err(e) => {
+                    delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+                    warn!(
+                        "Failed to reconnect to {} lock backend, retrying in {}s: {}",
+                        backend,
+                        delay.as_secs(),
+                        e
+                    );
                 }
             }
-            Err(e) => {
-                warn!("Could not get initial lock state: {}", e);
+        }
+    }
+}
+
+/// logind is Linux-only, so the backend is reported unavailable elsewhere and
+/// the next configured backend is tried
+#[cfg(not(target_os = "linux"))]
+async fn subscribe_logind(
+    _tx: mpsc::Sender<LockEvent>,
+    _resync: bool,
+) -> Result<BoxFuture<'static, ()>, BoxError> {
+    Err("lock detection via logind is unsupported on this platform".into())
+}
+
+/// Subscribe to logind Lock/Unlock signals for our session. The returned
+/// future also ends when that session is removed or another one becomes ours,
+/// e.g. after logging out and back in, so `reconnect` binds to the new one.
+#[cfg(target_os = "linux")]
+async fn subscribe_logind(
+    tx: mpsc::Sender<LockEvent>,
+    resync: bool,
+) -> Result<BoxFuture<'static, ()>, BoxError> {
+    let connection = Connection::system().await?;
+    let manager = LoginManagerProxy::new(&connection).await?;
+
+    let session_path = get_session_path(&connection, &manager).await?;
+    tracing::debug!("Monitoring session: {}", session_path);
+
+    let proxy = SessionProxy::builder(&connection)
+        .path(session_path.clone())?
+        .build()
+        .await?;
+
+    // Check initial lock state
+    match proxy.locked_hint().await {
+        Ok(locked) => {
+            if locked {
+                info!("Session is currently locked");
+                let _ = tx.send(LockEvent::Locked).await;
+            } else if resync {
+                let _ = tx.send(LockEvent::Unlocked).await;
             }
         }
+        Err(e) => {
+            warn!("Could not get initial lock state: {}", e);
+        }
+    }
 
-        let tx_lock = self.tx.clone();
-        let tx_unlock = self.tx.clone();
+    let mut lock_stream = proxy.receive_lock().await?;
+    let mut unlock_stream = proxy.receive_unlock().await?;
+    let mut new_stream = manager.receive_session_new().await?;
+    let mut removed_stream = manager.receive_session_removed().await?;
 
-        // Subscribe to Lock signal
-        let mut lock_stream = proxy.receive_lock().await?;
-        let lock_handle = tokio::spawn(async move {
-            while let Some(_) = lock_stream.next().await {
-                info!("Screen locked");
-                if tx_lock.send(LockEvent::Locked).await.is_err() {
-                    break;
+    Ok(Box::pin(async move {
+        loop {
+            let event = tokio::select! {
+                Some(_) = lock_stream.next() => LockEvent::Locked,
+                Some(_) = unlock_stream.next() => LockEvent::Unlocked,
+                Some(signal) = removed_stream.next() => {
+                    if signal.args().is_ok_and(|args| args.object_path.as_str() == session_path) {
+                        info!("Session {} ended, looking for a new one", session_path);
+                        break;
+                    }
+                    continue;
                 }
-            }
-        });
-
-        // Subscribe to Unlock signal
-        let mut unlock_stream = proxy.receive_unlock().await?;
-        let unlock_handle = tokio::spawn(async move {
-            while let Some(_) = unlock_stream.next().await {
-                info!("Screen unlocked");
-                if tx_unlock.send(LockEvent::Unlocked).await.is_err() {
-                    break;
+                Some(_) = new_stream.next() => {
+                    match get_session_path(&connection, &manager).await {
+                        Ok(path) if path != session_path => {
+                            info!("Session changed to {}, switching to it", path);
+                            break;
+                        }
+                        _ => continue,
+                    }
                 }
+                else => break,
+            };
+            match event {
+                LockEvent::Locked => info!("Screen locked"),
+                LockEvent::Unlocked => info!("Screen unlocked"),
             }
-        });
-
-        // Wait for either to complete (shouldn't happen unless connection drops)
-        tokio::select! {
-            _ = lock_handle => {
-                error!("Lock signal stream ended unexpectedly");
-            }
-            _ = unlock_handle => {
-                error!("Unlock signal stream ended unexpectedly");
+            if tx.send(event).await.is_err() {
+                break;
             }
         }
+    }))
+}
+
+/// Subscribe to org.freedesktop.ScreenSaver ActiveChanged on the session bus,
+/// offered by most X11 desktops and screen lockers
+async fn subscribe_screensaver(
+    tx: mpsc::Sender<LockEvent>,
+    resync: bool,
+) -> Result<BoxFuture<'static, ()>, BoxError> {
+    let connection = Connection::session().await?;
+    let proxy = ScreenSaverLockProxy::new(&connection).await?;
 
-        Ok(())
+    // Doubles as an availability check
+    if proxy.get_active().await? {
+        info!("Screen saver is currently active");
+        let _ = tx.send(LockEvent::Locked).await;
+    } else if resync {
+        let _ = tx.send(LockEvent::Unlocked).await;
     }
+
+    let mut stream = proxy.receive_active_changed().await?;
+
+    Ok(Box::pin(async move {
+        while let Some(signal) = stream.next().await {
+            let event = match signal.args() {
+                Ok(args) if args.active => LockEvent::Locked,
+                Ok(_) => LockEvent::Unlocked,
+                Err(e) => {
+                    warn!("Ignoring malformed ActiveChanged signal: {}", e);
+                    continue;
+                }
+            };
+            match event {
+                LockEvent::Locked => info!("Screen saver activated"),
+                LockEvent::Unlocked => info!("Screen saver deactivated"),
+            }
+            if tx.send(event).await.is_err() {
+                break;
+            }
+        }
+    }))
 }
 
-async fn get_session_path(connection: &Connection) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    // Try to get XDG_SESSION_ID first
+/// Our current session: `XDG_SESSION_ID` while that session lives, else
+/// the one our process belongs to, else the user's graphical session, which
+/// covers a daemon run as a systemd user service and a fresh login
+#[cfg(target_os = "linux")]
+async fn get_session_path(
+    connection: &Connection,
+    manager: &LoginManagerProxy<'_>,
+) -> Result<String, BoxError> {
     if let Ok(session_id) = std::env::var("XDG_SESSION_ID") {
-        return Ok(format!("/org/freedesktop/login1/session/{}", session_id));
+        if let Ok(path) = manager.get_session(&session_id).await {
+            return Ok(path.to_string());
+        }
+    }
+
+    if let Ok(path) = manager.get_session_by_pid(std::process::id()).await {
+        return Ok(path.to_string());
     }
 
-    // Fall back to getting the session by PID
-    let reply = connection
-        .call_method(
-            Some("org.freedesktop.login1"),
-            "/org/freedesktop/login1",
-            Some("org.freedesktop.login1.Manager"),
-            "GetSessionByPID",
-            &(std::process::id()),
-        )
+    let user_path = manager.get_user(unsafe { libc::getuid() }).await?;
+    let user = LoginUserProxy::builder(connection)
+        .path(user_path)?
+        .build()
         .await?;
+    let (_, display) = user.display().await?;
+    if display.as_str() == "/" {
+        return Err("no logind session found for this user".into());
+    }
+    Ok(display.to_string())
+}
+
+/// The logind session the logind backend would follow, for `mbell doctor`
+#[cfg(target_os = "linux")]
+pub async fn logind_session() -> Result<String, BoxError> {
+    let connection = Connection::system().await?;
+    let manager = LoginManagerProxy::new(&connection).await?;
+    get_session_path(&connection, &manager).await
+}
 
-    let session_path: zbus::zvariant::OwnedObjectPath = reply.body().deserialize()?;
-    Ok(session_path.to_string())
+#[cfg(not(target_os = "linux"))]
+pub async fn logind_session() -> Result<String, BoxError> {
+    Err("logind is unsupported on this platform".into())
 }
 
 /// Handle for the lock monitor that can be used to abort its tasks on shutdown
@@ -132,11 +347,13 @@ impl LockMonitorHandle {
 }
 
 /// Start the lock monitor in a background task
-pub fn start_lock_monitor() -> (mpsc::Receiver<LockEvent>, LockMonitorHandle) {
+pub fn start_lock_monitor(
+    backends: Vec<LockBackend>,
+) -> (mpsc::Receiver<LockEvent>, LockMonitorHandle) {
     let (tx, rx) = mpsc::channel(10);
 
     let task = tokio::spawn(async move {
-        let monitor = LockMonitor::new(tx);
+        let monitor = LockMonitor::new(tx, backends);
         if let Err(e) = monitor.run().await {
             error!("Lock monitor error: {}", e);
         }
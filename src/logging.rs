@@ -1,26 +1,200 @@
-use tracing::Level;
+use crate::config::LogBackend;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{warn, Level};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{fmt, EnvFilter};
 
-pub fn init(log_level: &str) {
-    let level = match log_level.to_lowercase().as_str() {
-        "error" => Level::ERROR,
-        "warn" => Level::WARN,
-        "info" => Level::INFO,
-        "debug" => Level::DEBUG,
-        "trace" => Level::TRACE,
-        _ => Level::INFO,
+/// Levels from quietest to loudest, for `-v` to step through
+const LEVELS: [Level; 5] = [Level::ERROR, Level::WARN, Level::INFO, Level::DEBUG, Level::TRACE];
+
+/// Environment variables holding filter directives that replace `log_level`,
+/// in order of precedence
+const FILTER_VARS: [&str; 2] = ["MBELL_LOG", "RUST_LOG"];
+
+/// Log to `backend`, and to `log_file` if given, rotated daily as
+/// `<name>.YYYY-MM-DD` and keeping at most `max_files` of them. The stderr
+/// backend only writes to stderr if `stderr` is set. See `filter` for how
+/// `log_level` and `verbose` combine with the environment.
+pub fn init(
+    log_level: &str,
+    verbose: u8,
+    backend: LogBackend,
+    log_file: Option<&Path>,
+    max_files: Option<usize>,
+    stderr: bool,
+) {
+    let filter = filter(log_level, verbose);
+
+    let file_layer = log_file.and_then(|path| match file_appender(path, max_files) {
+        Ok(appender) => Some(
+            fmt::layer()
+                .with_writer(appender)
+                .with_ansi(false)
+                .with_target(false),
+        ),
+        Err(e) => {
+            eprintln!("Failed to open log file {}: {}", path.display(), e);
+            None
+        }
+    });
+
+    let mut journald_error = None;
+    let journald_layer = match backend {
+        LogBackend::Journald => match tracing_journald::layer() {
+            Ok(layer) => Some(
+                layer
+                    .with_syslog_identifier("mbell".to_string())
+                    .with_field_prefix(None),
+            ),
+            Err(e) => {
+                journald_error = Some(e);
+                None
+            }
+        },
+        LogBackend::Stderr | LogBackend::File => None,
     };
 
-    let filter = EnvFilter::from_default_env()
-        .add_directive(format!("mbell={}", level).parse().unwrap())
-        .add_directive("zbus=warn".parse().unwrap())
-        .add_directive("rodio=warn".parse().unwrap());
-
-    fmt()
-        .with_env_filter(filter)
-        .with_target(false)
-        .with_thread_ids(false)
-        .with_file(false)
-        .with_line_number(false)
+    let wants_stderr = match backend {
+        LogBackend::Stderr => stderr,
+        LogBackend::File => false,
+        LogBackend::Journald => journald_layer.is_none(),
+    };
+    // Without a usable log file or journal, stderr is the only place left
+    let nowhere_else = file_layer.is_none() && journald_layer.is_none();
+    let stderr_layer = (wants_stderr || nowhere_else).then(|| {
+        fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_target(false)
+            .with_thread_ids(false)
+            .with_file(false)
+            .with_line_number(false)
+    });
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(stderr_layer)
+        .with(file_layer)
+        .with(journald_layer)
         .init();
+
+    if let Some(e) = journald_error {
+        warn!("journald is unavailable ({}), logging to stderr", e);
+    }
+}
+
+/// The log filter, from the first of: `log_level` raised a level per
+/// `verbose`, when given; the directives in `MBELL_LOG` or `RUST_LOG`, taken
+/// as they are; `log_level`. An invalid variable is reported and skipped.
+fn filter(log_level: &str, verbose: u8) -> EnvFilter {
+    if verbose == 0 {
+        for var in FILTER_VARS {
+            let Ok(directives) = std::env::var(var) else {
+                continue;
+            };
+            if directives.trim().is_empty() {
+                continue;
+            }
+            match EnvFilter::try_new(&directives) {
+                Ok(filter) => return filter,
+                Err(e) => eprintln!("Ignoring {}={:?}: {}", var, directives, e),
+            }
+        }
+    }
+
+    let configured = LEVELS
+        .iter()
+        .position(|level| level.as_str().eq_ignore_ascii_case(log_level))
+        .unwrap_or(2);
+    let level = LEVELS[(configured + verbose as usize).min(LEVELS.len() - 1)];
+    EnvFilter::new(format!("error,mbell={},zbus=warn,rodio=warn", level))
+}
+
+fn file_appender(
+    path: &Path,
+    max_files: Option<usize>,
+) -> Result<RollingFileAppender, Box<dyn std::error::Error>> {
+    let name = path.file_name().ok_or("log_file has no file name")?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::fs::create_dir_all(dir)?;
+
+    let mut builder = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(name.to_string_lossy());
+    if let Some(n) = max_files {
+        builder = builder.max_log_files(n);
+    }
+    Ok(builder.build(dir)?)
+}
+
+/// Throttles a repeating failure message so a chronic problem doesn't flood
+/// the log. The first occurrence (or a changed message) is reported
+/// immediately; repeats are reported every `every_n` occurrences or after
+/// `interval`, whichever comes first, with a count of the ones in between.
+pub struct LogLimiter {
+    every_n: u64,
+    interval: Duration,
+    state: Mutex<LimiterState>,
+}
+
+struct LimiterState {
+    suppressed: u64,
+    last_logged: Option<Instant>,
+    last_message: Option<String>,
+}
+
+impl LogLimiter {
+    pub const fn new(every_n: u64, interval: Duration) -> Self {
+        Self {
+            every_n,
+            interval,
+            state: Mutex::new(LimiterState {
+                suppressed: 0,
+                last_logged: None,
+                last_message: None,
+            }),
+        }
+    }
+
+    /// Record an occurrence, returning the text to log if it should be reported
+    pub fn check(&self, message: impl Into<String>) -> Option<String> {
+        let message = message.into();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        let is_new = state.last_message.as_deref() != Some(message.as_str());
+        let is_due = state.suppressed + 1 >= self.every_n
+            || state
+                .last_logged
+                .is_some_and(|at| at.elapsed() >= self.interval);
+
+        if !is_new && !is_due {
+            state.suppressed += 1;
+            return None;
+        }
+
+        let output = if !is_new && state.suppressed > 0 {
+            format!("{} ({} more since last report)", message, state.suppressed)
+        } else {
+            message.clone()
+        };
+
+        state.suppressed = 0;
+        state.last_logged = Some(Instant::now());
+        state.last_message = Some(message);
+        Some(output)
+    }
+
+    /// Forget the previous failure, so the next one is reported immediately
+    pub fn reset(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.suppressed = 0;
+        state.last_logged = None;
+        state.last_message = None;
+    }
 }
@@ -1,9 +1,16 @@
-use clap::{Parser, Subcommand};
-use mbell::config::Config;
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use mbell::audio::{AudioPlayer, Sound};
+use mbell::config::{Config, LockBackend};
 use mbell::daemon::Daemon;
-use mbell::ipc::{Command, IpcClient, Response};
+use mbell::ipc::{self, Command, IpcClient, IpcError, Response, StatusInfo};
+use mbell::schedule;
 use mbell::stats::Stats;
+use chrono::{Datelike, Local, NaiveDate};
+use std::path::PathBuf;
 use std::process::Command as ProcessCommand;
+use std::sync::OnceLock;
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(name = "mbell")]
@@ -11,8 +18,31 @@ use std::process::Command as ProcessCommand;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+    /// Keep retrying for up to this many seconds while the daemon starts up
+    #[arg(long, global = true, value_name = "SECS", num_args = 0..=1, default_missing_value = "5")]
+    wait: Option<u64>,
+    /// Print status, stats and config as JSON, and errors as {"error": ...} on stderr
+    #[arg(long, global = true)]
+    json: bool,
+    /// Make the daemon log more: -v for a level above log_level, -vv for two
+    ///
+    /// The daemon's log filter comes from the first of: -v, raising the
+    /// config file's log_level; the MBELL_LOG environment variable; RUST_LOG;
+    /// log_level. MBELL_LOG and RUST_LOG take full filter directives, e.g.
+    /// mbell=debug,zbus=info.
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
 }
 
+/// Retry window for IPC commands, set from `--wait`
+static IPC_WAIT: OnceLock<Duration> = OnceLock::new();
+
+/// Whether `--json` was given
+static JSON_OUTPUT: OnceLock<bool> = OnceLock::new();
+
+/// How many times `-v` was given
+static VERBOSITY: OnceLock<u8> = OnceLock::new();
+
 #[derive(Subcommand)]
 enum Commands {
     /// Start the daemon
@@ -20,6 +50,15 @@ enum Commands {
         /// Run in background (detached)
         #[arg(short, long)]
         detach: bool,
+        /// Override the configured interval for this run, e.g. 20m or 1h
+        #[arg(long, value_name = "DURATION", value_parser = parse_interval)]
+        interval: Option<u64>,
+        /// Refuse stop and pause for this many minutes (SIGTERM still works)
+        #[arg(long, value_name = "MINS", value_parser = clap::value_parser!(u64).range(1..))]
+        commit: Option<u64>,
+        /// Start running, ignoring a pause or snooze kept from the last run
+        #[arg(long)]
+        reset_state: bool,
     },
     /// Stop the running daemon
     Stop,
@@ -27,16 +66,127 @@ enum Commands {
     Pause,
     /// Resume the bell
     Resume,
+    /// Re-read the config file in the running daemon
+    Reload,
     /// Show daemon status and next bell time
-    Status,
+    Status {
+        /// Print "stopped" and exit successfully when the daemon isn't running
+        #[arg(short, long, visible_alias = "once")]
+        quiet: bool,
+        /// Print one line from a template instead, e.g. "{state} {next_mm_ss}".
+        /// Placeholders: {state}, {next_secs}, {next_mm_ss}, {interval},
+        /// {session_bells}, {phase}, {phase_mm_ss}, {profile}; {{ and }} for
+        /// literal braces
+        #[arg(long, value_name = "TEMPLATE")]
+        format: Option<String>,
+    },
+    /// Print only the time until the next bell, for status bars: -1 when
+    /// the daemon isn't running, its state when no bell is scheduled
+    Next {
+        /// How to print the time left
+        #[arg(long, value_enum, default_value = "secs")]
+        format: NextFormat,
+    },
+    /// Show a live countdown to the next bell until Ctrl+C
+    Watch,
+    /// Restart the countdown to a full interval without ringing
+    Reset,
+    /// Skip the next scheduled bell
+    Skip,
+    /// Delay the next bell until this many minutes from now
+    Snooze {
+        #[arg(value_parser = clap::value_parser!(u64).range(1..))]
+        mins: u64,
+    },
+    /// Change a setting in the running daemon
+    Set {
+        #[command(subcommand)]
+        setting: Setting,
+    },
+    /// Session commands
+    Session {
+        #[command(subcommand)]
+        action: SessionAction,
+    },
+    /// Switch to a named profile from the config
+    Profile {
+        /// Profile name, as in [profiles.<name>]
+        name: String,
+    },
+    /// Mark yourself away, pausing the bell as if the screen were locked
+    Away {
+        /// Mark yourself back instead
+        #[arg(long)]
+        back: bool,
+    },
     /// Show statistics
     Stats {
         /// Reset all statistics
         #[arg(long)]
         reset: bool,
+        /// Render the last year of practice as an SVG heatmap
+        #[arg(long, conflicts_with = "reset")]
+        graph: bool,
+        /// Write the heatmap to this file instead of stdout
+        #[arg(short, long, requires = "graph")]
+        output: Option<PathBuf>,
+        /// Back up the stats file now, pruning to `stats_backup_keep` backups
+        #[arg(long, conflicts_with_all = ["reset", "graph"])]
+        backup_now: bool,
+        /// Write statistics to stdout as CSV (bells per day) or JSON
+        #[arg(long, value_name = "FORMAT", conflicts_with_all = ["reset", "graph", "backup_now"])]
+        export: Option<ExportFormat>,
+        /// Only count days on or after this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date, conflicts_with_all = ["reset", "graph", "backup_now"])]
+        since: Option<NaiveDate>,
+        /// Only count days on or before this date (YYYY-MM-DD)
+        #[arg(long, value_name = "DATE", value_parser = parse_date, conflicts_with_all = ["reset", "graph", "backup_now"])]
+        until: Option<NaiveDate>,
+        /// List recent sessions with their start time, length and bells
+        #[arg(long, conflicts_with_all = ["reset", "graph", "backup_now", "export", "since", "until"])]
+        sessions: bool,
     },
     /// Ring the bell immediately
-    Ring,
+    Ring {
+        /// Only ring if the daemon is running and not paused, locked or suppressed
+        #[arg(long)]
+        if_running_active: bool,
+        /// Ring at this volume (0-100) instead of the configured one
+        #[arg(long, value_parser = clap::value_parser!(u8).range(0..=100))]
+        volume: Option<u8>,
+    },
+    /// Show the available bell sounds
+    Sounds {
+        /// List sounds with their durations (the default)
+        #[arg(long)]
+        list: bool,
+        /// Play each sound in turn at the configured volume
+        #[arg(long, conflicts_with = "list")]
+        test: bool,
+    },
+    /// Audio output commands
+    Audio {
+        /// List output device names, for `audio_device` (the default)
+        #[arg(long)]
+        list_devices: bool,
+        /// List the sound file formats this build can decode
+        #[arg(long, conflicts_with = "list_devices")]
+        formats: bool,
+    },
+    /// Check the config, sounds, audio output, socket directory and logind
+    /// session, with hints for anything that would stop the bell working
+    Doctor,
+    /// List when bells would ring under the current config, without ringing
+    Simulate {
+        /// How far ahead to look
+        #[arg(long, default_value_t = 24, value_parser = clap::value_parser!(u64).range(1..=8784))]
+        hours: u64,
+    },
+    /// Print a shell completion script to stdout
+    #[command(hide = true)]
+    Completions {
+        shell: Shell,
+    },
     /// Configuration commands
     Config {
         /// Open config in $EDITOR
@@ -45,32 +195,161 @@ enum Commands {
         /// Print config file path
         #[arg(long)]
         path: bool,
+        /// Show the running daemon's config, including runtime changes,
+        /// instead of the file
+        #[arg(long, conflicts_with_all = ["edit", "path"])]
+        live: bool,
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum NextFormat {
+    /// Whole seconds, e.g. 754
+    Secs,
+    /// Minutes and seconds, e.g. 12:34
+    #[value(name = "mm:ss")]
+    MmSs,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ExportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Subcommand)]
+enum Setting {
+    /// Set the interval, starting a fresh one from now, e.g. 20m or 1h
+    Interval {
+        #[arg(value_name = "DURATION", value_parser = parse_interval)]
+        interval: u64,
+        /// Also save the new interval to the config file
+        #[arg(long)]
+        persist: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum SessionAction {
+    /// Record the current session in stats and start a new one
+    New,
+}
+
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
+    if let Some(secs) = cli.wait {
+        let _ = IPC_WAIT.set(Duration::from_secs(secs));
+    }
+    let _ = JSON_OUTPUT.set(cli.json);
+    let _ = VERBOSITY.set(cli.verbose);
+    if !matches!(cli.command, Commands::Completions { .. }) {
+        // The CLI looks for the socket wherever the config puts the daemon's
+        ipc::init_socket(Config::load().map(|c| c.socket).unwrap_or_default());
+    }
 
     match cli.command {
-        Commands::Start { detach } => cmd_start(detach).await,
+        Commands::Start {
+            detach,
+            interval,
+            commit,
+            reset_state,
+        } => cmd_start(detach, interval, commit, reset_state).await,
         Commands::Stop => cmd_stop().await,
         Commands::Pause => cmd_pause().await,
         Commands::Resume => cmd_resume().await,
-        Commands::Status => cmd_status().await,
-        Commands::Stats { reset } => cmd_stats(reset).await,
-        Commands::Ring => cmd_ring().await,
-        Commands::Config { edit, path } => cmd_config(edit, path),
+        Commands::Reload => cmd_reload().await,
+        Commands::Status { quiet, format } => cmd_status(quiet, format).await,
+        Commands::Next { format } => cmd_next(format).await,
+        Commands::Watch => cmd_watch().await,
+        Commands::Reset => cmd_reset().await,
+        Commands::Skip => cmd_skip().await,
+        Commands::Snooze { mins } => cmd_snooze(mins).await,
+        Commands::Set { setting } => cmd_set(setting).await,
+        Commands::Session { action } => cmd_session(action).await,
+        Commands::Profile { name } => cmd_profile(name).await,
+        Commands::Away { back } => cmd_away(back).await,
+        Commands::Stats {
+            reset,
+            graph,
+            output,
+            backup_now,
+            export,
+            since,
+            until,
+            sessions,
+        } => {
+            if sessions {
+                cmd_sessions().await
+            } else {
+                cmd_stats(reset, graph, output, backup_now, export, since, until).await
+            }
+        }
+        Commands::Ring {
+            if_running_active,
+            volume,
+        } => cmd_ring(if_running_active, volume).await,
+        Commands::Sounds { list: _, test } => cmd_sounds(test),
+        Commands::Audio {
+            list_devices: _,
+            formats,
+        } => cmd_audio(formats),
+        Commands::Doctor => cmd_doctor().await,
+        Commands::Simulate { hours } => cmd_simulate(hours),
+        Commands::Completions { shell } => cmd_completions(shell),
+        Commands::Config { edit, path, live } => cmd_config(edit, path, live).await,
     }
 }
 
-async fn cmd_start(detach: bool) {
-    if IpcClient::is_daemon_running() {
-        eprintln!("Daemon is already running");
-        std::process::exit(1);
+/// Send a command to the daemon, retrying during startup if `--wait` was given
+async fn send_command(command: Command) -> Result<Response, IpcError> {
+    // Only `stop` expects the daemon to be on its way out; anything else
+    // reports it like any other refusal
+    let stopping = matches!(command, Command::Stop);
+    let response = match IPC_WAIT.get() {
+        Some(&wait) => IpcClient::send_command_retry(command, wait).await?,
+        None => IpcClient::send_command(command).await?,
+    };
+    match response {
+        Response::ShuttingDown if !stopping => {
+            Ok(Response::Error("Daemon is shutting down".to_string()))
+        }
+        response => Ok(response),
     }
+}
 
-    let config = match Config::load() {
+fn json_output() -> bool {
+    JSON_OUTPUT.get().copied().unwrap_or(false)
+}
+
+/// Report an error and exit, as `{"error": ...}` on stderr with `--json`
+fn fail(context: &str, error: impl std::fmt::Display) -> ! {
+    if json_output() {
+        eprintln!("{}", serde_json::json!({ "error": error.to_string() }));
+    } else {
+        eprintln!("{}: {}", context, error);
+    }
+    std::process::exit(1);
+}
+
+fn parse_interval(input: &str) -> Result<u64, String> {
+    match mbell::config::parse_minutes(input)? {
+        0 => Err("interval must be greater than 0".to_string()),
+        mins => Ok(mins),
+    }
+}
+
+fn parse_date(input: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(input, "%Y-%m-%d")
+        .map_err(|_| format!("expected a date like 2026-01-31, got '{}'", input))
+}
+
+async fn cmd_start(detach: bool, interval: Option<u64>, commit: Option<u64>, reset_state: bool) {
+    if let Some(pid) = ipc::running_daemon_pid() {
+        eprintln!("Daemon is already running (pid {})", pid);
+        std::process::exit(1);
+    }
+    let config = match Config::load_checked(true) {
         Ok(c) => c,
         Err(e) => {
             eprintln!("Failed to load config: {}", e);
@@ -85,9 +364,18 @@ async fn cmd_start(detach: bool) {
             .start()
         {
             Ok(_) => {
-                // We're now in the child process
-                mbell::logging::init(&config.log_level);
-                let daemon = Daemon::new(config);
+                // We're now in the child process, where stderr goes nowhere
+                init_logging(&config, false);
+                let mut daemon = Daemon::new(config);
+                if let Some(mins) = interval {
+                    daemon.override_interval(mins);
+                }
+                if let Some(mins) = commit {
+                    daemon.commit_for(Duration::from_secs(mins * 60));
+                }
+                if reset_state {
+                    daemon.reset_state();
+                }
                 if let Err(e) = daemon.run().await {
                     tracing::error!("Daemon error: {}", e);
                 }
@@ -99,9 +387,18 @@ async fn cmd_start(detach: bool) {
         }
     } else {
         // Run in foreground
-        mbell::logging::init(&config.log_level);
+        init_logging(&config, true);
         println!("Starting mbell daemon (Ctrl+C to stop)");
-        let daemon = Daemon::new(config);
+        let mut daemon = Daemon::new(config);
+        if let Some(mins) = interval {
+            daemon.override_interval(mins);
+        }
+        if let Some(mins) = commit {
+            daemon.commit_for(Duration::from_secs(mins * 60));
+        }
+        if reset_state {
+            daemon.reset_state();
+        }
         if let Err(e) = daemon.run().await {
             eprintln!("Daemon error: {}", e);
             std::process::exit(1);
@@ -109,9 +406,22 @@ async fn cmd_start(detach: bool) {
     }
 }
 
+fn init_logging(config: &Config, stderr: bool) {
+    mbell::logging::init(
+        &config.log_level,
+        VERBOSITY.get().copied().unwrap_or(0),
+        config.log_backend,
+        config.log_file.as_deref(),
+        config.log_max_files,
+        stderr,
+    );
+}
+
 async fn cmd_stop() {
-    match IpcClient::send_command(Command::Stop).await {
+    match send_command(Command::Stop).await {
         Ok(Response::Ok) => println!("Daemon stopped"),
+        // Another stop got there first
+        Ok(Response::ShuttingDown) => println!("Daemon is already stopping"),
         Ok(Response::Error(e)) => {
             eprintln!("Error: {}", e);
             std::process::exit(1);
@@ -125,7 +435,7 @@ async fn cmd_stop() {
 }
 
 async fn cmd_pause() {
-    match IpcClient::send_command(Command::Pause).await {
+    match send_command(Command::Pause).await {
         Ok(Response::Ok) => println!("Bell paused"),
         Ok(Response::Error(e)) => {
             eprintln!("Error: {}", e);
@@ -140,7 +450,7 @@ async fn cmd_pause() {
 }
 
 async fn cmd_resume() {
-    match IpcClient::send_command(Command::Resume).await {
+    match send_command(Command::Resume).await {
         Ok(Response::Ok) => println!("Bell resumed"),
         Ok(Response::Error(e)) => {
             eprintln!("Error: {}", e);
@@ -154,56 +464,442 @@ async fn cmd_resume() {
     }
 }
 
-async fn cmd_status() {
-    match IpcClient::send_command(Command::Status).await {
+async fn cmd_reload() {
+    match send_command(Command::Reload).await {
+        Ok(Response::Ok) => println!("Configuration reloaded"),
+        Ok(Response::Error(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Failed to reload: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn cmd_status(quiet: bool, format: Option<String>) {
+    // Catch a bad template whether or not the daemon is running
+    if let Some(Err(e)) = format.as_deref().map(|template| render_status(template, None)) {
+        fail("Invalid --format", e);
+    }
+
+    match send_command(Command::Status).await {
+        Ok(Response::Status(info)) if format.is_some() => {
+            let template = format.as_deref().unwrap_or_default();
+            match render_status(template, Some(&info)) {
+                Ok(line) => println!("{}", line),
+                Err(e) => fail("Invalid --format", e),
+            }
+        }
+        Ok(Response::Status(info)) if json_output() => match serde_json::to_string(&info) {
+            Ok(json) => println!("{}", json),
+            Err(e) => fail("Failed to serialize status", e),
+        },
         Ok(Response::Status(info)) => {
             println!("Status:     {}", info.state);
-            println!("Interval:   {} minutes", info.interval_mins);
+            if let Some(profile) = &info.profile {
+                println!("Profile:    {}", profile);
+            }
+            if info.interval_overridden {
+                println!("Interval:   {} minutes (override)", info.interval_mins);
+            } else if info.interval_scheduled {
+                println!("Interval:   {} minutes (scheduled)", info.interval_mins);
+            } else {
+                println!("Interval:   {} minutes", info.interval_mins);
+            }
             if let Some(secs) = info.next_bell_secs {
                 let mins = secs / 60;
                 let remaining_secs = secs % 60;
                 println!("Next bell:  {}:{:02}", mins, remaining_secs);
             } else {
-                println!("Next bell:  (paused)");
+                println!("Next bell:  ({})", info.state);
+            }
+            if let (Some(phase), Some(secs)) = (&info.phase, info.phase_remaining_secs) {
+                println!("Phase:      {} ({}:{:02} left)", phase, secs / 60, secs % 60);
+            }
+            if info.skip_pending {
+                println!("Skip:       next bell will be skipped");
+            }
+            if info.in_quiet_hours {
+                println!("Quiet:      in quiet hours, scheduled bells are silent");
+            }
+            if info.daily_cap_reached {
+                println!("Daily cap:  reached, no more scheduled bells today");
+            }
+            if let Some(secs) = info.committed_secs {
+                println!("Committed:  {}:{:02} remaining", secs / 60, secs % 60);
+            }
+            if let Some(secs) = info.present_secs {
+                println!("Present:    {}:{:02}", secs / 60, secs % 60);
+            }
+            if let Some(modality) = &info.modality {
+                println!("Modality:   {}", modality);
+            }
+            if let Some(message) = &info.next_message {
+                println!("Message:    {}", message);
             }
             println!("Session:    {} bells", info.total_bells_session);
         }
+        Ok(Response::Error(e)) => fail("Error", e),
+        Ok(_) => {}
+        Err(_) if quiet && format.is_some() => {
+            let template = format.as_deref().unwrap_or_default();
+            match render_status(template, None) {
+                Ok(line) => println!("{}", line),
+                Err(e) => fail("Invalid --format", e),
+            }
+        }
+        Err(_) if quiet && json_output() => println!("{}", serde_json::json!({ "state": "stopped" })),
+        Err(_) if quiet => println!("stopped"),
+        Err(e) => fail("Daemon not running", e),
+    }
+}
+
+/// `template` with each `{placeholder}` filled in from `info`, or as for a
+/// stopped daemon without it. Unknown placeholders are an error.
+fn render_status(template: &str, info: Option<&StatusInfo>) -> Result<String, String> {
+    let mut line = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                line.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                line.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err(format!("unclosed {{{}", name)),
+                    }
+                }
+                match status_field(&name, info) {
+                    Some(value) => line.push_str(&value),
+                    None => return Err(format!("unknown placeholder {{{}}}", name)),
+                }
+            }
+            '}' => return Err("unmatched }, write }} for a literal brace".to_string()),
+            c => line.push(c),
+        }
+    }
+    Ok(line)
+}
+
+/// Value of the `{name}` placeholder for `mbell status --format`, or `None`
+/// for an unknown name. Times fall back to the state when no bell is due,
+/// and fields a stopped daemon can't report are empty.
+fn status_field(name: &str, info: Option<&StatusInfo>) -> Option<String> {
+    let state = info.map_or("stopped", |info| info.state.as_str()).to_string();
+    let next = info.and_then(|info| info.next_bell_secs);
+    let phase_left = info.and_then(|info| info.phase_remaining_secs);
+    let value = match name {
+        "state" => state,
+        "next_secs" => next.map_or(state, |secs| secs.to_string()),
+        "next_mm_ss" => next.map_or(state, |secs| format!("{}:{:02}", secs / 60, secs % 60)),
+        "interval" => info.map(|info| info.interval_mins.to_string()).unwrap_or_default(),
+        "session_bells" => info
+            .map(|info| info.total_bells_session.to_string())
+            .unwrap_or_default(),
+        "phase" => info.and_then(|info| info.phase.clone()).unwrap_or_default(),
+        "phase_mm_ss" => phase_left
+            .map(|secs| format!("{}:{:02}", secs / 60, secs % 60))
+            .unwrap_or_default(),
+        "profile" => info.and_then(|info| info.profile.clone()).unwrap_or_default(),
+        _ => return None,
+    };
+    Some(value)
+}
+
+async fn cmd_next(format: NextFormat) {
+    match send_command(Command::Status).await {
+        Ok(Response::Status(info)) => match (info.next_bell_secs, format) {
+            (Some(secs), NextFormat::Secs) => println!("{}", secs),
+            (Some(secs), NextFormat::MmSs) => println!("{}:{:02}", secs / 60, secs % 60),
+            (None, _) => println!("{}", info.state),
+        },
+        Ok(Response::Error(e)) => fail("Error", e),
+        Ok(_) => {}
+        Err(_) => println!("-1"),
+    }
+}
+
+/// How often `mbell watch` refreshes the countdown
+const WATCH_REFRESH: Duration = Duration::from_secs(1);
+
+async fn cmd_watch() {
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let mut tick = tokio::time::interval(WATCH_REFRESH);
+    let mut watching = false;
+
+    loop {
+        tokio::select! {
+            _ = tick.tick() => {}
+            _ = &mut ctrl_c => break,
+        }
+
+        let line = match send_command(Command::Status).await {
+            Ok(Response::Status(info)) => {
+                let next = match info.next_bell_secs {
+                    Some(secs) => format!("next bell in {}:{:02}", secs / 60, secs % 60),
+                    None => "no bell scheduled".to_string(),
+                };
+                format!("{} | every {} min | {}", info.state, info.interval_mins, next)
+            }
+            Ok(Response::Error(e)) => format!("Error: {}", e),
+            Ok(_) => continue,
+            Err(e) => {
+                if watching {
+                    println!();
+                    eprintln!("Daemon went away: {}", e);
+                } else {
+                    eprintln!("Daemon not running: {}", e);
+                }
+                std::process::exit(1);
+            }
+        };
+
+        // Redraw in place: back to the start of the line, then clear it
+        print!("\r\x1b[K{}", line);
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+        watching = true;
+    }
+
+    if watching {
+        println!();
+    }
+}
+
+async fn cmd_reset() {
+    match send_command(Command::ResetTimer).await {
+        Ok(Response::Ok) => println!("Timer reset"),
+        Ok(Response::Error(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Failed to reset timer: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn cmd_skip() {
+    match send_command(Command::Skip).await {
+        Ok(Response::Ok) => println!("Next bell will be skipped"),
+        Ok(Response::Error(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Failed to skip: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn cmd_snooze(mins: u64) {
+    match send_command(Command::Snooze(mins)).await {
+        Ok(Response::Ok) => println!("Bell snoozed"),
+        Ok(Response::Error(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Failed to snooze: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn cmd_set(setting: Setting) {
+    let Setting::Interval { interval, persist } = setting;
+    match send_command(Command::SetInterval(interval, persist)).await {
+        Ok(Response::Ok) if persist => println!("Interval set to {} minutes and saved", interval),
+        Ok(Response::Ok) => println!("Interval set to {} minutes", interval),
         Ok(Response::Error(e)) => {
             eprintln!("Error: {}", e);
             std::process::exit(1);
         }
         Ok(_) => {}
         Err(e) => {
-            eprintln!("Daemon not running: {}", e);
+            eprintln!("Failed to set interval: {}", e);
             std::process::exit(1);
         }
     }
 }
 
-async fn cmd_stats(reset: bool) {
-    if reset {
+async fn cmd_session(action: SessionAction) {
+    let SessionAction::New = action;
+    match send_command(Command::NewSession).await {
+        Ok(Response::Ok) => println!("New session started"),
+        Ok(Response::Error(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Failed to start a new session: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn cmd_profile(name: String) {
+    match send_command(Command::SetProfile(name.clone())).await {
+        Ok(Response::Ok) => println!("Switched to profile {}", name),
+        Ok(Response::Error(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Failed to switch profile: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn cmd_away(back: bool) {
+    match send_command(Command::SetAway(!back)).await {
+        Ok(Response::Ok) if back => println!("Marked back"),
+        Ok(Response::Ok) => println!("Marked away"),
+        Ok(Response::Error(e)) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        Ok(_) => {}
+        Err(e) => {
+            eprintln!("Failed to set away: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+async fn cmd_stats(
+    reset: bool,
+    graph: bool,
+    output: Option<PathBuf>,
+    backup_now: bool,
+    export: Option<ExportFormat>,
+    since: Option<NaiveDate>,
+    until: Option<NaiveDate>,
+) {
+    if let (Some(since), Some(until)) = (since, until) {
+        if since > until {
+            fail(
+                "Invalid date range",
+                format!("--since {} is after --until {}", since, until),
+            );
+        }
+    }
+
+    if since.is_some() || until.is_some() {
+        let range = live_stats().await.range(since, until);
+        if let Some(ExportFormat::Csv) = export {
+            print!("{}", range.to_csv());
+        } else if json_output() || export.is_some() {
+            match range.to_json() {
+                Ok(json) => println!("{}", json),
+                Err(e) => fail("Failed to serialize stats", e),
+            }
+        } else {
+            println!("{}", range.display());
+        }
+    } else if backup_now {
+        let keep = Config::load().unwrap_or_default().stats_backup_keep;
+        match Stats::backup(keep).await {
+            Ok(Some(path)) => println!("Stats backed up to {}", path.display()),
+            Ok(None) => println!("No stats to back up yet"),
+            Err(e) => fail("Failed to back up stats", e),
+        }
+    } else if reset {
         let mut stats = Stats::load().unwrap_or_default();
         if let Err(e) = stats.reset().await {
-            eprintln!("Failed to reset stats: {}", e);
-            std::process::exit(1);
+            fail("Failed to reset stats", e);
         }
         println!("Statistics reset");
-    } else {
-        let stats = match Stats::load() {
-            Ok(s) => s,
-            Err(e) => {
-                eprintln!("Failed to load stats: {}", e);
-                std::process::exit(1);
+    } else if graph {
+        let boundary = Config::load().unwrap_or_default().day_boundary;
+        let today = mbell::stats::logical_date(Local::now(), boundary);
+        let svg = mbell::heatmap::render_svg(&load_stats().daily_counts, today);
+        match output {
+            Some(path) => {
+                if let Err(e) = std::fs::write(&path, svg) {
+                    fail(&format!("Failed to write {}", path.display()), e);
+                }
+                println!("Heatmap written to {}", path.display());
             }
-        };
-        println!("{}", stats.display());
+            None => print!("{}", svg),
+        }
+    } else if let Some(ExportFormat::Csv) = export {
+        print!("{}", live_stats().await.to_csv());
+    } else if json_output() || export.is_some() {
+        match live_stats().await.to_json() {
+            Ok(json) => println!("{}", json),
+            Err(e) => fail("Failed to serialize stats", e),
+        }
+    } else {
+        println!("{}", load_stats().display());
     }
 }
 
-async fn cmd_ring() {
+async fn cmd_sessions() {
+    let stats = live_stats().await;
+    if json_output() {
+        match stats.sessions_json() {
+            Ok(json) => println!("{}", json),
+            Err(e) => fail("Failed to serialize sessions", e),
+        }
+    } else {
+        println!("{}", stats.sessions_display());
+    }
+}
+
+/// The daemon's live stats, falling back to the file
+async fn live_stats() -> Stats {
+    match send_command(Command::GetStats).await {
+        Ok(Response::Stats(stats)) => stats,
+        _ => load_stats(),
+    }
+}
+
+fn load_stats() -> Stats {
+    match Stats::load() {
+        Ok(s) => s,
+        Err(e) => fail("Failed to load stats", e),
+    }
+}
+
+async fn cmd_ring(if_running_active: bool, volume: Option<u8>) {
+    if if_running_active {
+        match send_command(Command::Status).await {
+            Ok(Response::Status(info)) if info.state == "running" => {}
+            Ok(Response::Status(info)) => {
+                println!("Bell not rung: daemon is {}", info.state);
+                return;
+            }
+            _ => {
+                println!("Bell not rung: daemon not running");
+                return;
+            }
+        }
+    }
+
     // First try to send to daemon if running
     if IpcClient::is_daemon_running() {
-        match IpcClient::send_command(Command::Ring).await {
+        match send_command(Command::Ring { volume }).await {
             Ok(Response::Ok) => {
                 println!("Bell rung");
                 return;
@@ -219,26 +915,331 @@ async fn cmd_ring() {
         }
     }
 
-    // Ring directly if daemon not running
-    let config = Config::load().unwrap_or_default();
-    if let Err(e) = mbell::audio::ring(config.volume) {
+    // Ring directly if daemon not running, applying the same config and
+    // manual-ring policy the daemon would
+    let config = schedule::startup_config(
+        &Config::load().unwrap_or_default(),
+        Local::now().weekday(),
+    );
+    if config.quiet_hours_block_manual && config.in_quiet_hours(Local::now().time()) {
+        eprintln!("Error: Manual rings are blocked during quiet hours");
+        std::process::exit(1);
+    }
+    if config.notify {
+        // Best-effort, as in the daemon; there's no session, so this is bell 1
+        let body = mbell::notify::render_body(
+            &config.notify_body,
+            1,
+            config.messages.first().map(String::as_str),
+        );
+        if let Err(e) = mbell::notify::send(&body).await {
+            eprintln!("Warning: failed to show notification: {}", e);
+        }
+    }
+    if let Err(e) = mbell::audio::ring(&config, volume) {
         eprintln!("Failed to play bell: {}", e);
         std::process::exit(1);
     }
     println!("Bell rung");
 }
 
-fn cmd_config(edit: bool, path: bool) {
-    let config_path = match Config::config_path() {
-        Ok(p) => p,
+fn cmd_sounds(test: bool) {
+    if test {
+        test_sounds();
+        return;
+    }
+
+    let duration = match mbell::audio::bowl_duration() {
+        Some(d) => format!("{:.1}s", d.as_secs_f32()),
+        None => "unknown length".to_string(),
+    };
+    println!("bowl (embedded)  {}", duration);
+
+    let config = Config::load().unwrap_or_default();
+    let events = [
+        ("bell", Sound::Bell),
+        ("pause", Sound::Pause),
+        ("resume", Sound::Resume),
+        ("session start", Sound::SessionStart),
+    ];
+    for (event, sound) in events {
+        let Some(path) = sound.path(&config) else {
+            continue;
+        };
+        match mbell::audio::sound_file_duration(path) {
+            Some(d) => println!("{}  {:.1}s ({})", path.display(), d.as_secs_f32(), event),
+            None => println!("{}  unreadable, using the embedded bowl ({})", path.display(), event),
+        }
+    }
+}
+
+fn cmd_completions(shell: Shell) {
+    let mut cli = Cli::command();
+    let name = cli.get_name().to_string();
+    clap_complete::generate(shell, &mut cli, name, &mut std::io::stdout());
+}
+
+fn cmd_audio(formats: bool) {
+    if formats {
+        for format in mbell::audio::SOUND_FORMATS {
+            let extensions: Vec<String> =
+                format.extensions.iter().map(|ext| format!(".{}", ext)).collect();
+            let support = if format.available {
+                "built in".to_string()
+            } else {
+                format!("not built in (cargo feature {})", format.features)
+            };
+            println!("{:<12}{:<12}{}", format.name, extensions.join(" "), support);
+        }
+        return;
+    }
+
+    let names = match mbell::audio::output_device_names() {
+        Ok(names) => names,
         Err(e) => {
-            eprintln!("Failed to get config path: {}", e);
+            eprintln!("Failed to list audio devices: {}", e);
             std::process::exit(1);
         }
     };
+    if names.is_empty() {
+        println!("No audio output devices found");
+    }
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+/// Outcome of one `mbell doctor` check
+enum Check {
+    Pass(String),
+    /// Works, but not as configured, with a hint
+    Warn(String, String),
+    /// Stops the bell working, with a hint
+    Fail(String, String),
+}
+
+async fn cmd_doctor() {
+    let mut checks = Vec::new();
+
+    let config = match Config::load() {
+        Ok(config) => {
+            let path = Config::config_path().map(|p| p.display().to_string());
+            checks.push(("config", Check::Pass(path.unwrap_or_default())));
+            config
+        }
+        Err(e) => {
+            checks.push((
+                "config",
+                Check::Fail(
+                    e.to_string(),
+                    "Fix it with `mbell config --edit`; the checks below use the defaults".to_string(),
+                ),
+            ));
+            Config::default()
+        }
+    };
+
+    let sounds = config.sound_files();
+    if sounds.is_empty() {
+        checks.push(("sound", Check::Pass("embedded bowl".to_string())));
+    }
+    for (key, path) in sounds {
+        checks.push((
+            "sound",
+            match mbell::audio::check_sound(path) {
+                Ok(()) => Check::Pass(format!("{} = {}", key, path.display())),
+                Err(e) => Check::Fail(
+                    format!("{}: {}", key, e),
+                    format!(
+                        "Use a format listed by `mbell audio --formats`, or remove {} for the embedded bowl",
+                        key
+                    ),
+                ),
+            },
+        ));
+    }
+
+    checks.push((
+        "audio",
+        match mbell::audio::check_output(&config) {
+            Ok(()) if config.volume == 0 => Check::Warn(
+                "output works, but volume = 0 keeps bells silent".to_string(),
+                "Raise volume in the config".to_string(),
+            ),
+            Ok(()) => Check::Pass("output device opens".to_string()),
+            Err(e) => Check::Fail(
+                e.to_string(),
+                "Make sure PipeWire, PulseAudio or ALSA is running; `mbell audio --list-devices` \
+                 shows what's found"
+                    .to_string(),
+            ),
+        },
+    ));
+    if let Some(wanted) = &config.audio_device {
+        if !mbell::audio::output_device_exists(wanted) {
+            checks.push((
+                "audio",
+                Check::Warn(
+                    format!("no device matching audio_device {:?}, the default is used", wanted),
+                    "Pick a name from `mbell audio --list-devices`".to_string(),
+                ),
+            ));
+        }
+    }
+
+    checks.push(("runtime", check_runtime_dir()));
+
+    if config.lock_backends.contains(&LockBackend::Logind) {
+        checks.push((
+            "logind",
+            match mbell::lock::logind_session().await {
+                Ok(path) => Check::Pass(format!("session {}", path)),
+                Err(e) => Check::Warn(
+                    format!("no session to follow: {}", e),
+                    "Lock detection falls back to the next lock_backends entry; \
+                     `mbell away` works with any"
+                        .to_string(),
+                ),
+            },
+        ));
+    }
+
+    let mut failed = 0;
+    for (name, check) in &checks {
+        match check {
+            Check::Pass(detail) => println!("ok    {:<9}{}", name, detail),
+            Check::Warn(detail, hint) => {
+                println!("warn  {:<9}{}", name, detail);
+                println!("      {:<9}{}", "", hint);
+            }
+            Check::Fail(detail, hint) => {
+                failed += 1;
+                println!("FAIL  {:<9}{}", name, detail);
+                println!("      {:<9}{}", "", hint);
+            }
+        }
+    }
+    if failed > 0 {
+        eprintln!("{} check(s) failed", failed);
+        std::process::exit(1);
+    }
+}
+
+/// Whether the daemon can create its socket where the CLI will look for it
+fn check_runtime_dir() -> Check {
+    let dir = match ipc::socket_address() {
+        ipc::SocketAddress::Abstract(name) => {
+            return Check::Pass(format!("abstract socket @{}, no directory needed", name));
+        }
+        ipc::SocketAddress::File(path) => path.parent().unwrap_or(path).to_path_buf(),
+    };
+
+    // Creating a file is the only reliable test of write access
+    let probe = dir.join(format!(".mbell-doctor-{}", std::process::id()));
+    if let Err(e) = std::fs::OpenOptions::new().write(true).create_new(true).open(&probe) {
+        return Check::Fail(
+            format!("can't write to {}: {}", dir.display(), e),
+            "Set XDG_RUNTIME_DIR to a directory you own, or socket = \"abstract\"".to_string(),
+        );
+    }
+    let _ = std::fs::remove_file(&probe);
+
+    match std::env::var_os("XDG_RUNTIME_DIR") {
+        Some(runtime) if dir == std::path::Path::new(&runtime) => {
+            Check::Pass(format!("{} (XDG_RUNTIME_DIR)", dir.display()))
+        }
+        _ => Check::Warn(
+            format!("XDG_RUNTIME_DIR isn't a usable directory, using {}", dir.display()),
+            "Log in through a session manager that sets it, e.g. systemd-logind".to_string(),
+        ),
+    }
+}
+
+/// Pause between sounds in `mbell sounds --test`
+const SOUND_TEST_GAP: Duration = Duration::from_secs(1);
+
+/// Play every sound the bell can make, in order, reporting any that fail
+fn test_sounds() {
+    let config = Config::load().unwrap_or_default();
+    let player = AudioPlayer::from_config(&config);
+    if player.is_silent() {
+        println!("Volume is 0, nothing will be heard");
+    }
+
+    let sounds = [
+        ("bowl", player.clone()),
+        ("bowl (streak record)", player.celebratory()),
+    ];
+
+    let mut failed = 0;
+    for (i, (name, player)) in sounds.iter().enumerate() {
+        if i > 0 {
+            std::thread::sleep(SOUND_TEST_GAP);
+        }
+        println!("Playing {}", name);
+        if let Err(e) = player.play() {
+            eprintln!("  {} failed: {}", name, e);
+            failed += 1;
+        }
+    }
+
+    if failed > 0 {
+        eprintln!("{} of {} sounds failed", failed, sounds.len());
+        std::process::exit(1);
+    }
+}
+
+fn cmd_simulate(hours: u64) {
+    let config = match Config::load() {
+        Ok(c) => c,
+        Err(e) => fail("Failed to load config", e),
+    };
+    let start = Local::now();
+    let until = start + chrono::Duration::hours(hours as i64);
+    let bells = match schedule::simulate(&config, config.default_profile.as_deref(), start, until) {
+        Ok(bells) => bells,
+        Err(e) => fail("Failed to simulate schedule", e),
+    };
+
+    if json_output() {
+        match serde_json::to_string_pretty(&bells) {
+            Ok(json) => println!("{}", json),
+            Err(e) => fail("Failed to serialize schedule", e),
+        }
+        return;
+    }
+
+    for bell in &bells {
+        let mut line = bell.at.format("%a %Y-%m-%d %H:%M:%S").to_string();
+        if let Some(phase) = bell.phase {
+            line.push_str(&format!("  {}", phase));
+        }
+        if let Some(reason) = bell.silenced {
+            line.push_str(&format!("  (silent: {})", reason));
+        }
+        println!("{}", line);
+    }
+    let silent = bells.iter().filter(|bell| bell.silenced.is_some()).count();
+    println!(
+        "{} bells in the next {} hours, {} silent",
+        bells.len() - silent,
+        hours,
+        silent
+    );
+}
+
+async fn cmd_config(edit: bool, path: bool, live: bool) {
+    let config_path = match Config::config_path() {
+        Ok(p) => p,
+        Err(e) => fail("Failed to get config path", e),
+    };
 
     if path {
-        println!("{}", config_path.display());
+        if json_output() {
+            println!("{}", serde_json::json!({ "path": config_path }));
+        } else {
+            println!("{}", config_path.display());
+        }
         return;
     }
 
@@ -271,17 +1272,221 @@ fn cmd_config(edit: bool, path: bool) {
     }
 
     // Show current config
-    let config = match Config::load() {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Failed to load config: {}", e);
-            std::process::exit(1);
+    let config = if live {
+        match send_command(Command::GetConfig).await {
+            Ok(Response::Config(config)) => *config,
+            Ok(Response::Error(e)) => fail("Failed to get live config", e),
+            Ok(_) => fail("Failed to get live config", "unexpected response"),
+            Err(e) => fail("Failed to get live config", e),
+        }
+    } else {
+        match Config::load() {
+            Ok(c) => c,
+            Err(e) => fail("Failed to load config", e),
         }
     };
+    if json_output() {
+        match serde_json::to_string_pretty(&config) {
+            Ok(json) => println!("{}", json),
+            Err(e) => fail("Failed to serialize config", e),
+        }
+        return;
+    }
 
+    println!("mode      = {}", match config.mode {
+        mbell::config::Mode::Interval => "interval",
+        mbell::config::Mode::Pomodoro => "pomodoro",
+    });
     println!("interval  = {}", config.interval);
+    if let Some(schedule) = &config.schedule {
+        let days: Vec<String> = schedule
+            .entries()
+            .map(|(day, mins)| format!("{} {}", day, mins))
+            .collect();
+        println!("schedule  = {}", days.join(", "));
+    }
+    if config.mode == mbell::config::Mode::Pomodoro {
+        let p = &config.pomodoro;
+        println!(
+            "pomodoro  = {}m work, {}m break, {}m long break every {} cycles",
+            p.work_mins, p.break_mins, p.long_break_mins, p.cycles_before_long_break
+        );
+    }
     println!("volume    = {}", config.volume);
+    if config.volume_variation > 0 {
+        println!("volume_variation = {}%", config.volume_variation);
+    }
+    match mbell::audio::resolved_sound(&config) {
+        Some(path) => println!("sound     = {}", path.display()),
+        None if Sound::Bell.path(&config).is_some() => {
+            println!("sound     = embedded bowl (sound file unreadable)")
+        }
+        None => println!("sound     = embedded bowl"),
+    }
+    if let Some(sounds) = &config.sounds {
+        let events = [
+            ("pause", &sounds.pause),
+            ("resume", &sounds.resume),
+            ("session_start", &sounds.session_start),
+        ];
+        for (event, path) in events {
+            match path {
+                Some(path) => println!("sounds.{} = {}", event, path.display()),
+                None => println!("sounds.{} = embedded bowl", event),
+            }
+        }
+    }
+    if let Some(device) = &config.audio_device {
+        println!("audio_device = {}", device);
+    }
+    if let Some(path) = &config.ambient_path {
+        println!("ambient_path = {} (volume {})", path.display(), config.ambient_volume);
+    }
+    if config.repeat > 1 {
+        println!(
+            "repeat    = {} strikes, {}s apart (at most {})",
+            config.repeat,
+            config.repeat_gap_secs,
+            mbell::config::MAX_REPEAT
+        );
+    } else {
+        println!("repeat    = 1 strike");
+    }
+    if config.pre_bell_secs > 0 {
+        println!("pre_bell_secs = {}", config.pre_bell_secs);
+    }
+    println!("gain_db   = {}", config.gain_db);
+    if config.fade_in_ms > 0 || config.fade_out_ms > 0 {
+        println!("fade      = {}ms in, {}ms out", config.fade_in_ms, config.fade_out_ms);
+    }
+    if config.jitter_secs > 0 {
+        match config.jitter_seed {
+            Some(seed) => println!("jitter_secs = {} (seed {})", config.jitter_secs, seed),
+            None => println!("jitter_secs = {}", config.jitter_secs),
+        }
+    }
     println!("log_level = {}", config.log_level);
+    println!("log_backend = {}", config.log_backend);
+    if let Some(path) = &config.log_file {
+        match config.log_max_files {
+            Some(n) => println!("log_file  = {} (keeping {} files)", path.display(), n),
+            None => println!("log_file  = {}", path.display()),
+        }
+    }
+    println!("require_presence = {}", config.require_presence);
+    println!("presence_idle_secs = {}", config.presence_idle_secs);
+    if let Some(secs) = config.idle_timeout_secs {
+        println!("idle_timeout_secs = {} ({} backend)", secs, config.idle_backend);
+    }
+    println!("ring_on_resume = {}", config.ring_on_resume);
+    println!("ring_on_start = {}", config.ring_on_start);
+    println!("count_start_bell = {}", config.count_start_bell);
+    println!("socket = {}", config.socket);
+    println!("socket_mode = {}", config.socket_mode);
+    let backends: Vec<String> = config.lock_backends.iter().map(|b| b.to_string()).collect();
+    println!("lock_backends = {}", backends.join(", "));
+    println!("pause_on_lock = {}", config.pause_on_lock);
+    if let Some(path) = &config.calendar_path {
+        println!("calendar_path = {}", path.display());
+    }
+    println!("celebrate_records = {}", config.celebrate_records);
+    println!("notify = {}", config.notify);
+    if config.notify {
+        println!("notify_body = {}", config.notify_body);
+    }
+    if !config.messages.is_empty() {
+        println!("messages = {}", config.messages.join(" | "));
+    }
+    if config.announce_count {
+        match &config.announce_command {
+            Some(command) => println!("announce_count = true ({})", command),
+            None => println!("announce_count = true (bowl strikes)"),
+        }
+    }
+    if let Some(escalation) = &config.snooze_escalation {
+        println!(
+            "snooze_escalation = -{}% interval, +{} volume per snooze, up to {} steps",
+            escalation.interval_step_pct, escalation.volume_step, escalation.max_steps
+        );
+    }
+    if let Some(ramp) = &config.audio_ramp {
+        let mut limits = Vec::new();
+        if let Some(bells) = ramp.after_bells {
+            limits.push(format!("{} bells", bells));
+        }
+        if let Some(mins) = ramp.after_mins {
+            limits.push(format!("{} minutes", mins));
+        }
+        println!("audio_ramp = silent after {}", limits.join(" or "));
+    }
+    if let Some(ramp) = &config.volume_ramp {
+        println!(
+            "volume_ramp = {} to {} over {} bells",
+            ramp.start, ramp.end, ramp.over_bells
+        );
+    }
+    if let Some(burst) = &config.opening_burst {
+        println!(
+            "opening_burst = {} strikes, {}s apart{}",
+            burst.count,
+            burst.gap_secs,
+            if burst.count_in_stats { ", counted in stats" } else { "" }
+        );
+    }
+    if let Some(mins) = config.auto_pause_after_mins {
+        println!("auto_pause_after_mins = {}", mins);
+    }
+    if config.max_bells_per_day > 0 {
+        println!("max_bells_per_day = {}", config.max_bells_per_day);
+    }
+    if let Some(at) = config.daily_summary_at {
+        println!("daily_summary_at = {}", at.format("%H:%M"));
+    }
+    if let Some(keep) = config.stats_backup_keep {
+        println!("stats_backup_keep = {}", keep);
+    }
+    println!("durable_stats = {}", config.durable_stats);
+    println!("session_history_keep = {}", config.session_history_keep);
+    println!("day_boundary = {}", config.day_boundary.format("%H:%M"));
+    if let Some(quiet) = &config.quiet_hours {
+        println!(
+            "quiet_hours = {} - {}",
+            quiet.start.format("%H:%M"),
+            quiet.end.format("%H:%M")
+        );
+    }
+    println!("quiet_hours_block_manual = {}", config.quiet_hours_block_manual);
+    if config.min_ring_gap_ms > 0 {
+        println!("min_ring_gap_ms = {}", config.min_ring_gap_ms);
+    }
+    if let Some(profile) = &config.default_profile {
+        println!("default_profile = {}", profile);
+    }
+    for (name, profile) in &config.profiles {
+        let mut overrides = Vec::new();
+        if let Some(interval) = profile.interval {
+            overrides.push(format!("interval {}", interval));
+        }
+        if let Some(volume) = profile.volume {
+            overrides.push(format!("volume {}", volume));
+        }
+        if let Some(gain_db) = profile.gain_db {
+            overrides.push(format!("gain_db {}", gain_db));
+        }
+        println!("profiles.{} = {}", name, overrides.join(", "));
+    }
+    for (name, pattern) in &config.patterns {
+        let hours = if pattern.hours.is_empty() {
+            "every hour".to_string()
+        } else {
+            let hours: Vec<String> = pattern.hours.iter().map(u32::to_string).collect();
+            format!("hours {}", hours.join(","))
+        };
+        println!(
+            "patterns.{} = {} strikes at :{:02}, {}",
+            name, pattern.repeat, pattern.minute, hours
+        );
+    }
     println!();
     println!("Config file: {}", config_path.display());
 }
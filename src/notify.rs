@@ -0,0 +1,77 @@
+use crate::logging::LogLimiter;
+use std::collections::HashMap;
+use std::time::Duration;
+use tracing::{debug, warn};
+use zbus::{proxy, zvariant::Value, Connection};
+
+/// Summary line shown on every bell notification
+const SUMMARY: &str = "Mindfulness bell";
+
+/// Let the notification server pick how long to show it
+const DEFAULT_TIMEOUT: i32 = -1;
+
+/// Notification failures, e.g. no session bus or notification server, are
+/// reported at most every 10 bells or once an hour while they persist
+static NOTIFY_FAILURES: LogLimiter = LogLimiter::new(10, Duration::from_secs(3600));
+
+#[proxy(
+    interface = "org.freedesktop.Notifications",
+    default_service = "org.freedesktop.Notifications",
+    default_path = "/org/freedesktop/Notifications"
+)]
+trait Notifications {
+    #[allow(clippy::too_many_arguments)]
+    fn notify(
+        &self,
+        app_name: &str,
+        replaces_id: u32,
+        app_icon: &str,
+        summary: &str,
+        body: &str,
+        actions: &[&str],
+        hints: HashMap<&str, Value<'_>>,
+        expire_timeout: i32,
+    ) -> zbus::Result<u32>;
+}
+
+/// Fill in `{count}` and `{message}` in a `notify_body` template
+pub fn render_body(template: &str, count: u64, message: Option<&str>) -> String {
+    template
+        .replace("{count}", &count.to_string())
+        .replace("{message}", message.unwrap_or_default())
+}
+
+/// Show a desktop notification via org.freedesktop.Notifications
+pub async fn send(body: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let connection = Connection::session().await?;
+    let proxy = NotificationsProxy::new(&connection).await?;
+    proxy
+        .notify(
+            "mbell",
+            0,
+            "",
+            SUMMARY,
+            body,
+            &[],
+            HashMap::new(),
+            DEFAULT_TIMEOUT,
+        )
+        .await?;
+    debug!("Notification sent: {}", body);
+    Ok(())
+}
+
+/// Send a notification in the background. Best-effort: failures are logged
+/// and never hold up the bell.
+pub fn send_async(body: String) {
+    tokio::spawn(async move {
+        match send(&body).await {
+            Ok(()) => NOTIFY_FAILURES.reset(),
+            Err(e) => {
+                if let Some(message) = NOTIFY_FAILURES.check(e.to_string()) {
+                    warn!("Failed to show notification: {}", message);
+                }
+            }
+        }
+    });
+}
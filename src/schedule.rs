@@ -0,0 +1,488 @@
+use crate::config::{Config, ConfigError, Mode};
+use crate::stats::logical_date;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Weekday};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Shortest interval jitter can leave
+pub const MIN_JITTERED_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Phase of a pomodoro cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PomodoroPhase {
+    Work,
+    Break,
+    LongBreak,
+}
+
+impl std::fmt::Display for PomodoroPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PomodoroPhase::Work => write!(f, "work"),
+            PomodoroPhase::Break => write!(f, "break"),
+            PomodoroPhase::LongBreak => write!(f, "long break"),
+        }
+    }
+}
+
+impl PomodoroPhase {
+    /// The phase that follows this one at a bell. `work_phases_done` counts
+    /// finished work phases, including this one if it's work.
+    pub fn next(self, work_phases_done: u32, cycles_before_long_break: u32) -> Self {
+        match self {
+            PomodoroPhase::Work if work_phases_done.is_multiple_of(cycles_before_long_break) => {
+                PomodoroPhase::LongBreak
+            }
+            PomodoroPhase::Work => PomodoroPhase::Break,
+            PomodoroPhase::Break | PomodoroPhase::LongBreak => PomodoroPhase::Work,
+        }
+    }
+}
+
+/// `base` with the `[schedule]` interval for `day`, then the profile on top
+pub fn effective_config(
+    base: &Config,
+    profile: Option<&str>,
+    day: Weekday,
+) -> Result<Config, ConfigError> {
+    let mut scheduled = base.clone();
+    scheduled.interval = base.interval_on(day);
+    match profile {
+        Some(name) => scheduled.with_profile(name),
+        None => Ok(scheduled),
+    }
+}
+
+/// `base` as a freshly started daemon uses it on `day`: the `[schedule]`
+/// interval and `default_profile` applied, or `base` itself if the profile
+/// is missing
+pub fn startup_config(base: &Config, day: Weekday) -> Config {
+    effective_config(base, base.default_profile.as_deref(), day).unwrap_or_else(|_| base.clone())
+}
+
+/// Time between bells under `config`, in pomodoro mode for `phase`
+pub fn interval_for(config: &Config, phase: PomodoroPhase) -> Duration {
+    let mins = match config.mode {
+        Mode::Interval => config.interval,
+        Mode::Pomodoro => match phase {
+            PomodoroPhase::Work => config.pomodoro.work_mins,
+            PomodoroPhase::Break => config.pomodoro.break_mins,
+            PomodoroPhase::LongBreak => config.pomodoro.long_break_mins,
+        },
+    };
+    Duration::from_secs(mins * 60)
+}
+
+/// `interval` with `jitter_secs` added or, when negative, taken off
+pub fn jittered(interval: Duration, jitter_secs: i64) -> Duration {
+    let jitter = Duration::from_secs(jitter_secs.unsigned_abs());
+    if jitter_secs < 0 {
+        interval.saturating_sub(jitter).max(MIN_JITTERED_INTERVAL)
+    } else {
+        interval + jitter
+    }
+}
+
+/// Pick the jitter for a new interval. Pomodoro phases keep their length.
+pub fn roll_jitter(config: &Config, rng: &mut impl Rng) -> i64 {
+    let max = config.jitter_secs as i64;
+    if max > 0 && config.mode == Mode::Interval {
+        rng.random_range(-max..=max)
+    } else {
+        0
+    }
+}
+
+/// The jitter source the daemon uses: seeded from `jitter_seed` when set
+pub fn jitter_rng(config: &Config) -> StdRng {
+    match config.jitter_seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_os_rng(),
+    }
+}
+
+/// The daemon's bell timer: when the current interval started, any snooze,
+/// its jitter, pomodoro progress and, in `require_presence` mode, the present
+/// time counted so far. It never reads the clock itself; callers pass `now`
+/// and the config currently in effect, so a reload applies immediately.
+pub struct Scheduler {
+    last_bell: Instant,
+    /// Deadline for the next bell when it has been snoozed
+    snooze_deadline: Option<Instant>,
+    /// Source of jitter, seeded from `jitter_seed` when set
+    rng: StdRng,
+    /// Jitter in seconds applied to the current interval
+    jitter_secs: i64,
+    phase: PomodoroPhase,
+    /// Work phases completed, for spacing out long breaks
+    work_phases_done: u32,
+    /// Time left in the pomodoro phase while its countdown is frozen
+    phase_remaining: Option<Duration>,
+    /// Present time accumulated toward the next bell
+    present: Duration,
+}
+
+impl Scheduler {
+    /// A timer whose first interval starts at `now`
+    pub fn new(config: &Config, now: Instant) -> Self {
+        let mut rng = jitter_rng(config);
+        let jitter_secs = roll_jitter(config, &mut rng);
+        Self {
+            last_bell: now,
+            snooze_deadline: None,
+            rng,
+            jitter_secs,
+            phase: PomodoroPhase::Work,
+            work_phases_done: 0,
+            phase_remaining: None,
+            present: Duration::ZERO,
+        }
+    }
+
+    pub fn phase(&self) -> PomodoroPhase {
+        self.phase
+    }
+
+    pub fn last_bell(&self) -> Instant {
+        self.last_bell
+    }
+
+    pub fn is_snoozed(&self) -> bool {
+        self.snooze_deadline.is_some()
+    }
+
+    pub fn snooze_deadline(&self) -> Option<Instant> {
+        self.snooze_deadline
+    }
+
+    pub fn present(&self) -> Duration {
+        self.present
+    }
+
+    /// Length of the current interval, before jitter
+    pub fn interval(&self, config: &Config) -> Duration {
+        interval_for(config, self.phase)
+    }
+
+    /// Length of the current interval with its jitter applied
+    pub fn scheduled_interval(&self, config: &Config) -> Duration {
+        jittered(self.interval(config), self.jitter_secs)
+    }
+
+    /// When the next bell is due on the wall-clock timer
+    pub fn next_bell_at(&self, config: &Config) -> Instant {
+        self.snooze_deadline
+            .unwrap_or(self.last_bell + self.scheduled_interval(config))
+    }
+
+    /// Time left until the bell: wall-clock time, or in `require_presence`
+    /// mode the present time still needed, unless snoozed
+    pub fn remaining(&self, config: &Config, now: Instant) -> Duration {
+        if config.require_presence && !self.is_snoozed() {
+            self.scheduled_interval(config).saturating_sub(self.present)
+        } else {
+            self.next_bell_at(config).saturating_duration_since(now)
+        }
+    }
+
+    /// Whether the bell is due at `now`
+    pub fn should_ring(&self, config: &Config, now: Instant) -> bool {
+        if config.require_presence && !self.is_snoozed() {
+            self.present >= self.scheduled_interval(config)
+        } else {
+            now >= self.next_bell_at(config)
+        }
+    }
+
+    /// Time left in the pomodoro phase, frozen while suspended
+    pub fn phase_remaining(&self, config: &Config, now: Instant) -> Duration {
+        self.phase_remaining
+            .unwrap_or_else(|| self.next_bell_at(config).saturating_duration_since(now))
+    }
+
+    /// Start a fresh interval at `now`, with new jitter
+    pub fn restart(&mut self, config: &Config, now: Instant) {
+        self.phase_remaining = None;
+        self.last_bell = now;
+        self.snooze_deadline = None;
+        self.present = Duration::ZERO;
+        self.jitter_secs = roll_jitter(config, &mut self.rng);
+    }
+
+    /// Ring at `deadline` instead, replacing any earlier snooze
+    pub fn snooze_until(&mut self, deadline: Instant) {
+        self.snooze_deadline = Some(deadline);
+    }
+
+    /// Count present time toward the next bell
+    pub fn add_presence(&mut self, elapsed: Duration) {
+        self.present += elapsed;
+    }
+
+    /// Start the present count over, returning whether any had built up
+    pub fn reset_presence(&mut self) -> bool {
+        !std::mem::take(&mut self.present).is_zero()
+    }
+
+    /// Move to the next pomodoro phase at a bell, returning it
+    pub fn advance_phase(&mut self, config: &Config) -> PomodoroPhase {
+        if self.phase == PomodoroPhase::Work {
+            self.work_phases_done += 1;
+        }
+        self.phase = self.phase.next(
+            self.work_phases_done,
+            config.pomodoro.cycles_before_long_break,
+        );
+        self.phase
+    }
+
+    /// Freeze the pomodoro countdown while the bell is paused or locked
+    pub fn suspend_phase(&mut self, config: &Config, now: Instant) {
+        if config.mode == Mode::Pomodoro && self.phase_remaining.is_none() {
+            self.phase_remaining = Some(self.next_bell_at(config).saturating_duration_since(now));
+        }
+    }
+
+    /// Continue a frozen pomodoro countdown, returning whether there was one
+    pub fn resume_phase(&mut self, config: &Config, now: Instant) -> bool {
+        let Some(remaining) = self.phase_remaining.take() else {
+            return false;
+        };
+        let elapsed = self.interval(config).saturating_sub(remaining);
+        self.snooze_deadline = None;
+        self.last_bell = now.checked_sub(elapsed).unwrap_or(now);
+        true
+    }
+}
+
+/// When the bell after one at `now` is due, with the interval starting then
+pub fn next_bell_after(
+    now: DateTime<Local>,
+    config: &Config,
+    phase: PomodoroPhase,
+    jitter_secs: i64,
+) -> DateTime<Local> {
+    let interval = jittered(interval_for(config, phase), jitter_secs);
+    now + chrono::Duration::from_std(interval).unwrap_or(chrono::Duration::MAX)
+}
+
+/// Time until the first audible bell after `now`, given the next one is due
+/// in `remaining` and each after it `interval` later. Bells that fall in
+/// quiet hours are skipped, each starting a fresh interval, so step forward
+/// an interval at a time until one lands outside the window.
+pub fn until_audible(
+    config: &Config,
+    now: DateTime<Local>,
+    remaining: Duration,
+    interval: Duration,
+) -> Duration {
+    let Some(quiet) = &config.quiet_hours else {
+        return remaining;
+    };
+
+    let mut due = remaining;
+    // A day's worth of steps covers any window
+    for _ in 0..=(24 * 60 / (interval.as_secs() / 60).max(1)) {
+        let at = now + chrono::Duration::from_std(due).unwrap_or_default();
+        if !quiet.contains(at.time()) {
+            return due;
+        }
+        due += interval;
+    }
+    remaining
+}
+
+/// A bell `simulate` expects the daemon to ring
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulatedBell {
+    pub at: DateTime<Local>,
+    /// Phase starting with this bell, in pomodoro mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub phase: Option<PomodoroPhase>,
+    /// Why the bell would stay silent, if it would
+    pub silenced: Option<&'static str>,
+}
+
+/// Every bell an undisturbed daemon started at `start` would ring until
+/// `until`, following its rules: each weekday's `[schedule]` under
+/// `profile`, jitter, pomodoro phases, quiet hours and `max_bells_per_day`.
+/// Jitter is only repeatable with `jitter_seed` set.
+pub fn simulate(
+    base: &Config,
+    profile: Option<&str>,
+    start: DateTime<Local>,
+    until: DateTime<Local>,
+) -> Result<Vec<SimulatedBell>, ConfigError> {
+    let mut rng = jitter_rng(base);
+    let mut phase = PomodoroPhase::Work;
+    let mut work_phases_done = 0;
+    let mut today: Option<NaiveDate> = None;
+    let mut bells_today = 0;
+    let mut bells = Vec::new();
+
+    let mut now = start;
+    loop {
+        let config = effective_config(base, profile, now.weekday())?;
+        let jitter = roll_jitter(&config, &mut rng);
+        now = next_bell_after(now, &config, phase, jitter);
+        if now > until {
+            break;
+        }
+
+        let date = logical_date(now, config.day_boundary);
+        if today != Some(date) {
+            today = Some(date);
+            bells_today = 0;
+        }
+
+        // Same order as the daemon: the cap holds the phase, quiet hours don't
+        let silenced = if config.max_bells_per_day > 0 && bells_today >= config.max_bells_per_day {
+            Some("daily cap")
+        } else {
+            if config.mode == Mode::Pomodoro {
+                if phase == PomodoroPhase::Work {
+                    work_phases_done += 1;
+                }
+                phase = phase.next(work_phases_done, config.pomodoro.cycles_before_long_break);
+            }
+            if config.in_quiet_hours(now.time()) {
+                Some("quiet hours")
+            } else {
+                bells_today += 1;
+                None
+            }
+        };
+
+        bells.push(SimulatedBell {
+            at: now,
+            phase: (config.mode == Mode::Pomodoro).then_some(phase),
+            silenced,
+        });
+    }
+    Ok(bells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::QuietHours;
+    use chrono::{NaiveTime, TimeZone};
+
+    const MIN: Duration = Duration::from_secs(60);
+
+    fn at(hour: u32, min: u32) -> DateTime<Local> {
+        Local
+            .with_ymd_and_hms(2026, 1, 5, hour, min, 0)
+            .earliest()
+            .unwrap()
+    }
+
+    fn quiet(start: u32, end: u32) -> Option<QuietHours> {
+        Some(QuietHours {
+            start: NaiveTime::from_hms_opt(start, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(end, 0, 0).unwrap(),
+        })
+    }
+
+    #[test]
+    fn snooze_and_restart_move_the_bell() {
+        let config = Config {
+            interval: 20,
+            ..Config::default()
+        };
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(&config, start);
+        assert_eq!(scheduler.next_bell_at(&config), start + 20 * MIN);
+        assert!(!scheduler.should_ring(&config, start + 19 * MIN));
+        assert!(scheduler.should_ring(&config, start + 20 * MIN));
+
+        scheduler.snooze_until(start + 25 * MIN);
+        assert_eq!(scheduler.next_bell_at(&config), start + 25 * MIN);
+        assert!(!scheduler.should_ring(&config, start + 20 * MIN));
+        assert!(scheduler.should_ring(&config, start + 25 * MIN));
+
+        scheduler.restart(&config, start + 21 * MIN);
+        assert!(!scheduler.is_snoozed());
+        assert_eq!(scheduler.next_bell_at(&config), start + 41 * MIN);
+    }
+
+    #[test]
+    fn suspended_phase_resumes_where_it_stopped() {
+        let mut config = Config {
+            mode: Mode::Pomodoro,
+            ..Config::default()
+        };
+        config.pomodoro.work_mins = 25;
+        let start = Instant::now();
+        let mut scheduler = Scheduler::new(&config, start);
+
+        scheduler.suspend_phase(&config, start + 10 * MIN);
+        assert_eq!(scheduler.phase_remaining(&config, start + 40 * MIN), 15 * MIN);
+
+        assert!(scheduler.resume_phase(&config, start + 60 * MIN));
+        assert_eq!(scheduler.next_bell_at(&config), start + 75 * MIN);
+        assert!(!scheduler.should_ring(&config, start + 74 * MIN));
+        assert!(scheduler.should_ring(&config, start + 75 * MIN));
+        assert!(!scheduler.resume_phase(&config, start + 80 * MIN));
+    }
+
+    #[test]
+    fn negative_jitter_keeps_a_minimum_interval() {
+        assert_eq!(jittered(20 * MIN, -60), 19 * MIN);
+        assert_eq!(jittered(20 * MIN, 60), 21 * MIN);
+        assert_eq!(jittered(MIN, -3600), MIN_JITTERED_INTERVAL);
+    }
+
+    #[test]
+    fn every_fourth_work_phase_ends_in_a_long_break() {
+        use PomodoroPhase::*;
+        assert_eq!(Work.next(1, 4), Break);
+        assert_eq!(Work.next(3, 4), Break);
+        assert_eq!(Work.next(4, 4), LongBreak);
+        assert_eq!(Work.next(8, 4), LongBreak);
+        assert_eq!(Break.next(4, 4), Work);
+        assert_eq!(LongBreak.next(4, 4), Work);
+    }
+
+    #[test]
+    fn quiet_bells_are_skipped_to_the_first_audible_one() {
+        let config = Config {
+            quiet_hours: quiet(22, 7),
+            ..Config::default()
+        };
+        let hour = 60 * MIN;
+        assert_eq!(until_audible(&config, at(21, 0), 30 * MIN, hour), 30 * MIN);
+        // 22:15 through 06:15 are quiet, 07:15 rings
+        assert_eq!(until_audible(&config, at(21, 45), 30 * MIN, hour), 9 * hour + 30 * MIN);
+    }
+
+    #[test]
+    fn simulate_applies_quiet_hours_and_the_daily_cap() {
+        let config = Config {
+            interval: 60,
+            max_bells_per_day: 3,
+            quiet_hours: quiet(8, 9),
+            ..Config::default()
+        };
+        let bells = simulate(&config, None, at(6, 30), at(12, 0)).unwrap();
+
+        let summary: Vec<_> = bells
+            .iter()
+            .map(|bell| (bell.at.format("%H:%M").to_string(), bell.silenced))
+            .collect();
+        // Quiet bells don't count toward the cap
+        assert_eq!(
+            summary,
+            [
+                ("07:30".to_string(), None),
+                ("08:30".to_string(), Some("quiet hours")),
+                ("09:30".to_string(), None),
+                ("10:30".to_string(), None),
+                ("11:30".to_string(), Some("daily cap")),
+            ]
+        );
+    }
+}
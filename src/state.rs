@@ -0,0 +1,61 @@
+use chrono::{DateTime, Utc};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use tokio::fs;
+use tracing::warn;
+
+/// What the daemon carries over a restart: an explicit pause and a pending
+/// snooze. Everything else starts fresh, and a locked screen is read again
+/// from the lock backend rather than saved.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedState {
+    /// Paused with `mbell pause`, including while the screen is locked
+    pub paused: bool,
+    /// Snoozed bell's deadline
+    pub snoozed_until: Option<DateTime<Utc>>,
+}
+
+impl SavedState {
+    /// `state.json` beside the stats, as it has to outlive a reboot
+    pub fn path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "mbell").map(|dirs| dirs.data_dir().join("state.json"))
+    }
+
+    /// The state a previous daemon left, if any. A damaged file is reported
+    /// and ignored.
+    pub fn load() -> Option<Self> {
+        let path = Self::path()?;
+        let contents = std::fs::read_to_string(&path).ok()?;
+        match serde_json::from_str(&contents) {
+            Ok(state) => Some(state),
+            Err(e) => {
+                warn!("Ignoring unreadable saved state {:?}: {}", path, e);
+                None
+            }
+        }
+    }
+
+    /// Write the state atomically, or remove the file when there's nothing
+    /// to restore
+    pub async fn save(&self) -> std::io::Result<()> {
+        let Some(path) = Self::path() else {
+            return Ok(());
+        };
+        if *self == Self::default() {
+            return match fs::remove_file(&path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+                _ => Ok(()),
+            };
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let temp_path = path.with_extension("json.tmp");
+        let contents = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        fs::write(&temp_path, contents).await?;
+        fs::rename(&temp_path, &path).await
+    }
+}
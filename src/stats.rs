@@ -1,12 +1,23 @@
-use chrono::{DateTime, Local, NaiveDate, Utc};
+use crate::logging::LogLimiter;
+use chrono::{DateTime, Local, NaiveDate, NaiveTime, Timelike, Utc};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::sync::OnceLock;
+use std::time::Duration;
 use thiserror::Error;
 use tokio::fs;
+use tokio::io::AsyncWriteExt;
 use tracing::{debug, warn};
 
+/// Version of the `mbell stats --json` schema, bumped on incompatible changes
+pub const STATS_SCHEMA_VERSION: u32 = 1;
+
+/// Repeated save failures, e.g. a read-only data dir, are reported at most
+/// every 10 occurrences or once an hour while they persist
+static SAVE_FAILURES: LogLimiter = LogLimiter::new(10, Duration::from_secs(3600));
+
 static PROJECT_DIRS: OnceLock<Option<ProjectDirs>> = OnceLock::new();
 
 fn get_project_dirs() -> Option<&'static ProjectDirs> {
@@ -37,9 +48,98 @@ pub struct Stats {
     pub longest_streak: u64,
     /// Last time the bell was rung
     pub last_ring: Option<DateTime<Utc>>,
-    /// Date of the last activity (for streak calculation)
+    /// Date of the last activity (for streak calculation). Internal, but
+    /// deliberately part of the JSON schema so consumers can tell whether
+    /// `current_streak` is still live today.
     #[serde(default)]
     last_active_date: Option<NaiveDate>,
+    /// Bells rung per local date
+    #[serde(default)]
+    pub daily_counts: BTreeMap<NaiveDate, u64>,
+    /// Seconds the daemon has spent running, not paused, locked or idle
+    #[serde(default)]
+    pub active_secs: u64,
+    /// Scheduled bells that didn't sound: skipped for quiet hours or the
+    /// daily cap, or rung with no audio output
+    #[serde(default)]
+    pub suppressed_bells: u64,
+    /// Finished sessions, oldest first, up to `session_history_keep`
+    #[serde(default)]
+    pub sessions: Vec<SessionRecord>,
+    /// Running time below a whole second, carried into the next `add_active`
+    #[serde(skip)]
+    active_carry: Duration,
+    /// Fsync the file and its directory on every save (`durable_stats`)
+    #[serde(skip)]
+    durable: bool,
+    /// Local time the practice day rolls over (`day_boundary`)
+    #[serde(skip)]
+    day_boundary: NaiveTime,
+}
+
+/// A finished session: from daemon start or `mbell session new` to the next
+/// new session or a clean shutdown
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionRecord {
+    pub started: DateTime<Utc>,
+    pub duration_secs: u64,
+    pub bells: u64,
+}
+
+/// Versioned wrapper used for `mbell stats --json` output
+#[derive(Serialize)]
+struct StatsJson<'a> {
+    schema_version: u32,
+    #[serde(flatten)]
+    stats: &'a Stats,
+}
+
+/// Totals over a window of `daily_counts`, for `mbell stats --since/--until`
+#[derive(Debug, Serialize)]
+pub struct StatsRange {
+    pub schema_version: u32,
+    /// First day of the window, if bounded
+    pub since: Option<NaiveDate>,
+    /// Last day of the window, if bounded
+    pub until: Option<NaiveDate>,
+    /// Bells rung within the window
+    pub total_bells: u64,
+    /// Days within the window with at least one bell
+    pub days_active: u64,
+    /// Longest run of consecutive active days within the window
+    pub longest_streak: u64,
+    /// Bells rung per local date within the window
+    pub daily_counts: BTreeMap<NaiveDate, u64>,
+}
+
+impl StatsRange {
+    pub fn to_json(&self) -> Result<String, StatsError> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// One `date,bells` row per active day in the window, oldest first
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,bells\n");
+        for (date, count) in &self.daily_counts {
+            csv.push_str(&format!("{},{}\n", date, count));
+        }
+        csv
+    }
+
+    pub fn display(&self) -> String {
+        let bound = |date: Option<NaiveDate>, open: &str| {
+            date.map_or_else(|| open.to_string(), |d| d.to_string())
+        };
+        let mut output = format!(
+            "Range:          {} to {}\n",
+            bound(self.since, "first bell"),
+            bound(self.until, "today")
+        );
+        output.push_str(&format!("Total bells:    {}\n", self.total_bells));
+        output.push_str(&format!("Days active:    {}\n", self.days_active));
+        output.push_str(&format!("Longest streak: {} days", self.longest_streak));
+        output
+    }
 }
 
 impl Stats {
@@ -83,26 +183,173 @@ impl Stats {
         // Write atomically by writing to temp file first
         let temp_path = path.with_extension("json.tmp");
         let contents = serde_json::to_string_pretty(self)?;
-        fs::write(&temp_path, &contents).await?;
+        if self.durable {
+            // The data must be on disk before the rename, or a crash could
+            // leave a renamed but empty file behind
+            let mut file = fs::File::create(&temp_path).await?;
+            file.write_all(contents.as_bytes()).await?;
+            file.sync_all().await?;
+        } else {
+            fs::write(&temp_path, &contents).await?;
+        }
         fs::rename(&temp_path, &path).await?;
 
+        if self.durable {
+            if let Some(parent) = path.parent() {
+                // tokio::fs has no way to sync a directory, so persist the
+                // rename itself with a blocking fsync on the parent
+                let parent = parent.to_path_buf();
+                tokio::task::spawn_blocking(move || std::fs::File::open(parent)?.sync_all())
+                    .await
+                    .map_err(std::io::Error::other)??;
+            }
+        }
+
         debug!("Stats saved successfully");
         Ok(())
     }
 
+    /// Copy the stats file to a timestamped file under `backups/`, then delete
+    /// all but the newest `keep` backups. Returns `None` if there is no stats
+    /// file to back up yet.
+    pub async fn backup(keep: Option<usize>) -> Result<Option<PathBuf>, StatsError> {
+        let path = Self::stats_path()?;
+        if !fs::try_exists(&path).await? {
+            return Ok(None);
+        }
+
+        let backup_dir = path.with_file_name("backups");
+        fs::create_dir_all(&backup_dir).await?;
+        let backup_path = backup_dir.join(format!(
+            "stats-{}.json",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        fs::copy(&path, &backup_path).await?;
+        debug!("Stats backed up to {:?}", backup_path);
+
+        if let Some(keep) = keep {
+            // Timestamped names sort chronologically
+            let mut backups = Vec::new();
+            let mut entries = fs::read_dir(&backup_dir).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                if name.starts_with("stats-") && name.ends_with(".json") {
+                    backups.push(entry.path());
+                }
+            }
+            backups.sort();
+            let excess = backups.len().saturating_sub(keep);
+            for old in &backups[..excess] {
+                fs::remove_file(old).await?;
+                debug!("Removed old stats backup {:?}", old);
+            }
+        }
+
+        Ok(Some(backup_path))
+    }
+
     pub fn stats_path() -> Result<PathBuf, StatsError> {
         get_project_dirs()
             .map(|dirs| dirs.data_dir().join("stats.json"))
             .ok_or(StatsError::NoDataDir)
     }
 
-    pub async fn record_bell(&mut self) {
-        let now = Utc::now();
-        let today = Local::now().date_naive();
+    /// Fsync on every save, trading speed for surviving power loss
+    pub fn set_durable(&mut self, durable: bool) {
+        self.durable = durable;
+    }
+
+    /// Start each practice day at `boundary` local time instead of midnight
+    pub fn set_day_boundary(&mut self, boundary: NaiveTime) {
+        self.day_boundary = boundary;
+    }
+
+    /// The practice day `now` falls on, given the day boundary
+    pub fn day_of(&self, now: DateTime<Local>) -> NaiveDate {
+        logical_date(now, self.day_boundary)
+    }
+
+    /// Record a bell rung at `now`, returning the new streak length if it
+    /// set a record
+    pub async fn record_bell(&mut self, now: DateTime<Local>) -> Option<u64> {
+        let new_record = self.count_bell(now);
+        self.save_or_warn().await;
+        new_record
+    }
+
+    /// Count a bell rung at `now` without saving, for callers that persist a
+    /// snapshot later with `save_or_warn`
+    pub fn count_bell(&mut self, now: DateTime<Local>) -> Option<u64> {
+        self.apply_bell(now.with_timezone(&Utc), self.day_of(now))
+    }
+
+    /// Record a scheduled bell that didn't sound
+    pub async fn record_suppressed(&mut self) {
+        self.suppressed_bells += 1;
+        self.save_or_warn().await;
+    }
+
+    /// Add a finished session without saving, dropping the oldest beyond
+    /// `keep`
+    pub fn record_session(&mut self, session: SessionRecord, keep: usize) {
+        self.sessions.push(session);
+        let excess = self.sessions.len().saturating_sub(keep);
+        self.sessions.drain(..excess);
+    }
 
+    /// Add time the daemon spent running, without saving
+    pub fn add_active(&mut self, elapsed: Duration) {
+        let total = self.active_carry + elapsed;
+        self.active_secs += total.as_secs();
+        self.active_carry = total - Duration::from_secs(total.as_secs());
+    }
+
+    /// Save, reporting failures through the rate-limited log
+    pub async fn save_or_warn(&self) {
+        match self.save().await {
+            Ok(()) => SAVE_FAILURES.reset(),
+            Err(e) => {
+                if let Some(message) = SAVE_FAILURES.check(e.to_string()) {
+                    warn!("Failed to save stats: {}", message);
+                }
+            }
+        }
+    }
+
+    /// One-line summary of `today`'s practice
+    pub fn daily_summary(&self, today: NaiveDate) -> String {
+        let bells = self.daily_counts.get(&today).copied().unwrap_or(0);
+        format!(
+            "Today: {} bell{}, current streak {} day{}",
+            bells,
+            if bells == 1 { "" } else { "s" },
+            self.current_streak,
+            if self.current_streak == 1 { "" } else { "s" }
+        )
+    }
+
+    /// Update counters and streaks for a bell rung at `now` on local date `today`
+    fn apply_bell(&mut self, now: DateTime<Utc>, today: NaiveDate) -> Option<u64> {
         self.total_bells += 1;
         self.last_ring = Some(now);
 
+        // A date earlier than the last active one means the clock went
+        // backwards (manual change, NTP correction, DST fall-back near
+        // midnight). Count the bell on the latest recorded day instead of
+        // treating it as a gap that breaks the streak. The same applies when
+        // `day_boundary` moves later, including dates recorded before it
+        // existed: a 1am bell after a 00:30 one stays on the recorded day.
+        let today = match self.last_active_date {
+            Some(last_date) if today < last_date => {
+                debug!(
+                    "Clock moved back from {} to {}, counting bell on {}",
+                    last_date, today, last_date
+                );
+                last_date
+            }
+            _ => today,
+        };
+
         // Update streak calculation
         if let Some(last_date) = self.last_active_date {
             let days_diff = (today - last_date).num_days();
@@ -125,29 +372,114 @@ impl Stats {
         }
 
         self.last_active_date = Some(today);
+        *self.daily_counts.entry(today).or_insert(0) += 1;
 
-        // Update longest streak if current is longer
+        // Update longest streak if current is longer. The streak only grows on
+        // the first bell of a day, so a record is reported once per day.
         if self.current_streak > self.longest_streak {
             self.longest_streak = self.current_streak;
-        }
-
-        if let Err(e) = self.save().await {
-            warn!("Failed to save stats: {}", e);
+            Some(self.current_streak)
+        } else {
+            None
         }
     }
 
     pub async fn reset(&mut self) -> Result<(), StatsError> {
-        *self = Stats::default();
+        *self = Stats {
+            durable: self.durable,
+            day_boundary: self.day_boundary,
+            ..Stats::default()
+        };
         self.save().await
     }
 
+    /// Serialize to the stable, versioned JSON schema
+    pub fn to_json(&self) -> Result<String, StatsError> {
+        let json = serde_json::to_string_pretty(&StatsJson {
+            schema_version: STATS_SCHEMA_VERSION,
+            stats: self,
+        })?;
+        Ok(json)
+    }
+
+    /// Restrict `daily_counts` to `since..=until`, either end open when `None`
+    pub fn range(&self, since: Option<NaiveDate>, until: Option<NaiveDate>) -> StatsRange {
+        let daily_counts: BTreeMap<NaiveDate, u64> = self
+            .daily_counts
+            .iter()
+            .filter(|(date, _)| {
+                since.is_none_or(|s| **date >= s) && until.is_none_or(|u| **date <= u)
+            })
+            .map(|(date, count)| (*date, *count))
+            .collect();
+
+        let mut longest_streak = 0;
+        let mut streak = 0;
+        let mut previous: Option<NaiveDate> = None;
+        for date in daily_counts.keys() {
+            streak = match previous {
+                Some(prev) if (*date - prev).num_days() == 1 => streak + 1,
+                _ => 1,
+            };
+            longest_streak = longest_streak.max(streak);
+            previous = Some(*date);
+        }
+
+        StatsRange {
+            schema_version: STATS_SCHEMA_VERSION,
+            since,
+            until,
+            total_bells: daily_counts.values().sum(),
+            days_active: daily_counts.len() as u64,
+            longest_streak,
+            daily_counts,
+        }
+    }
+
+    /// One `date,bells` row per day with at least one bell, oldest first
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("date,bells\n");
+        for (date, count) in &self.daily_counts {
+            csv.push_str(&format!("{},{}\n", date, count));
+        }
+        csv
+    }
+
+    /// The recorded sessions as a JSON array, oldest first
+    pub fn sessions_json(&self) -> Result<String, StatsError> {
+        Ok(serde_json::to_string_pretty(&self.sessions)?)
+    }
+
+    /// A table of the recorded sessions, oldest first
+    pub fn sessions_display(&self) -> String {
+        if self.sessions.is_empty() {
+            return "No finished sessions yet".to_string();
+        }
+        let mut output = format!("{:<18}{:<10}{}", "Started", "Duration", "Bells");
+        for session in &self.sessions {
+            let started: DateTime<Local> = session.started.into();
+            output.push_str(&format!(
+                "\n{:<18}{:<10}{}",
+                started.format("%Y-%m-%d %H:%M"),
+                humanize_secs(session.duration_secs),
+                session.bells
+            ));
+        }
+        output
+    }
+
     pub fn display(&self) -> String {
         let mut output = String::new();
 
         output.push_str(&format!("Total bells:    {}\n", self.total_bells));
+        output.push_str(&format!("Suppressed:     {}\n", self.suppressed_bells));
         output.push_str(&format!("Days active:    {}\n", self.days_active));
         output.push_str(&format!("Current streak: {} days\n", self.current_streak));
         output.push_str(&format!("Longest streak: {} days\n", self.longest_streak));
+        output.push_str(&format!(
+            "Mindful time:   {}\n",
+            humanize_secs(self.active_secs)
+        ));
 
         if let Some(last) = self.last_ring {
             let local: DateTime<Local> = last.into();
@@ -162,3 +494,89 @@ impl Stats {
         output
     }
 }
+
+/// The practice day `now` belongs to: its local date, counted as the day
+/// before until `boundary` has passed
+pub fn logical_date(now: DateTime<Local>, boundary: NaiveTime) -> NaiveDate {
+    let since_midnight = chrono::Duration::seconds(i64::from(boundary.num_seconds_from_midnight()));
+    (now.naive_local() - since_midnight).date()
+}
+
+/// A duration as hours and minutes, e.g. "12h 5m", or just minutes under an hour
+fn humanize_secs(secs: u64) -> String {
+    let mins = secs / 60;
+    match (mins / 60, mins % 60) {
+        (0, mins) => format!("{}m", mins),
+        (hours, mins) => format!("{}h {}m", hours, mins),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, MockClock};
+    use chrono::TimeZone;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, min, 0).earliest().unwrap()
+    }
+
+    #[test]
+    fn earlier_date_counts_on_last_active_day() {
+        let mut stats = Stats::default();
+        stats.apply_bell(Utc::now(), date(2026, 3, 1));
+        stats.apply_bell(Utc::now(), date(2026, 3, 2));
+        assert_eq!(stats.current_streak, 2);
+
+        stats.apply_bell(Utc::now(), date(2026, 2, 27));
+        assert_eq!(stats.current_streak, 2);
+        assert_eq!(stats.days_active, 2);
+        assert_eq!(stats.last_active_date, Some(date(2026, 3, 2)));
+        assert_eq!(stats.daily_counts.get(&date(2026, 3, 2)), Some(&2));
+        assert_eq!(stats.daily_counts.get(&date(2026, 2, 27)), None);
+        assert_eq!(stats.total_bells, 3);
+    }
+
+    #[test]
+    fn fall_back_past_midnight_keeps_the_streak() {
+        // Just after midnight the clock falls back an hour into the previous
+        // local date
+        let clock = MockClock::new(local(2026, 10, 24, 21, 0));
+        let mut stats = Stats::default();
+        stats.count_bell(clock.now_local());
+        clock.set_local(local(2026, 10, 25, 0, 30));
+        stats.count_bell(clock.now_local());
+        clock.set_local(local(2026, 10, 24, 23, 30));
+        stats.count_bell(clock.now_local());
+
+        assert_eq!(stats.current_streak, 2);
+        assert_eq!(stats.days_active, 2);
+        assert_eq!(stats.daily_counts.get(&date(2026, 10, 24)), Some(&1));
+        assert_eq!(stats.daily_counts.get(&date(2026, 10, 25)), Some(&2));
+
+        // The next day continues the streak as usual
+        clock.set_local(local(2026, 10, 26, 9, 0));
+        stats.count_bell(clock.now_local());
+        assert_eq!(stats.current_streak, 3);
+    }
+
+    #[test]
+    fn manual_clock_set_back_days_keeps_the_streak() {
+        let clock = MockClock::new(local(2026, 5, 10, 9, 0));
+        let mut stats = Stats::default();
+        stats.count_bell(clock.now_local());
+        clock.advance(Duration::from_secs(24 * 3600));
+        stats.count_bell(clock.now_local());
+
+        clock.set_local(local(2026, 5, 1, 9, 0));
+        assert_eq!(stats.count_bell(clock.now_local()), None);
+        assert_eq!(stats.current_streak, 2);
+        assert_eq!(stats.longest_streak, 2);
+        assert_eq!(stats.daily_counts.get(&date(2026, 5, 11)), Some(&2));
+        assert_eq!(stats.daily_counts.get(&date(2026, 5, 1)), None);
+    }
+}
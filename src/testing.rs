@@ -0,0 +1,27 @@
+//! Helpers shared by the unit tests
+
+use std::path::Path;
+use std::sync::OnceLock;
+use tempfile::TempDir;
+
+static HOME: OnceLock<TempDir> = OnceLock::new();
+
+/// Point the config, data and runtime dirs at a temp dir for the rest of
+/// the run, so tests never touch the real ones. Paths are resolved once per
+/// process, so every test that reaches them has to call this first.
+pub fn isolate() -> &'static Path {
+    HOME.get_or_init(|| {
+        let home = tempfile::tempdir().expect("temp dir");
+        for (var, dir) in [
+            ("XDG_CONFIG_HOME", "config"),
+            ("XDG_DATA_HOME", "data"),
+            ("XDG_RUNTIME_DIR", "run"),
+        ] {
+            let path = home.path().join(dir);
+            std::fs::create_dir_all(&path).expect("temp dir");
+            std::env::set_var(var, path);
+        }
+        home
+    })
+    .path()
+}
@@ -0,0 +1,144 @@
+//! A daemon running in-process, driven over its socket the way the CLI does
+
+use chrono::Local;
+use futures_util::future::join_all;
+use mbell::clock::MockClock;
+use mbell::config::Config;
+use mbell::daemon::Daemon;
+use mbell::ipc::{Command, IpcClient, Response};
+use mbell::stats::Stats;
+use std::future::Future;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::sync::Mutex;
+use tokio::time::timeout;
+
+/// Long enough for a slow machine, short enough that a hang fails the test
+const TEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+static HOME: OnceLock<TempDir> = OnceLock::new();
+
+/// Only one daemon can own the socket at a time
+static SERIAL: Mutex<()> = Mutex::const_new(());
+
+/// Point the config, data and runtime dirs at a temp dir for this process,
+/// before anything resolves them
+fn isolate() {
+    HOME.get_or_init(|| {
+        let home = tempfile::tempdir().expect("temp dir");
+        for (var, dir) in [
+            ("XDG_CONFIG_HOME", "config"),
+            ("XDG_DATA_HOME", "data"),
+            ("XDG_RUNTIME_DIR", "run"),
+        ] {
+            let path = home.path().join(dir);
+            std::fs::create_dir_all(&path).expect("temp dir");
+            std::env::set_var(var, path);
+        }
+        home
+    });
+}
+
+/// Run a silent daemon until `client`, which has to stop it, finishes
+async fn with_daemon<F: Future>(client: F) -> F::Output {
+    let config = Config {
+        volume: 0,
+        ..Config::default()
+    };
+    let daemon = Daemon::with_clock(config, Arc::new(MockClock::new(Local::now())));
+
+    let session = async {
+        let up = IpcClient::send_command_retry(Command::Status, TEST_TIMEOUT).await;
+        assert!(matches!(up, Ok(Response::Status(_))), "daemon didn't come up: {:?}", up);
+        client.await
+    };
+    let (result, output) = timeout(TEST_TIMEOUT, async { tokio::join!(daemon.run(), session) })
+        .await
+        .expect("daemon didn't stop");
+    if let Err(e) = result {
+        panic!("daemon failed: {}", e);
+    }
+    output
+}
+
+#[tokio::test]
+async fn ring_then_stop_saves_the_bell() {
+    isolate();
+    let _serial = SERIAL.lock().await;
+    if let Ok(path) = Stats::stats_path() {
+        let _ = std::fs::remove_file(path);
+    }
+
+    with_daemon(async {
+        let ring = IpcClient::send_command(Command::Ring { volume: None }).await;
+        assert!(matches!(ring, Ok(Response::Ok)), "{:?}", ring);
+        let stop = IpcClient::send_command(Command::Stop).await;
+        assert!(matches!(stop, Ok(Response::Ok)), "{:?}", stop);
+    })
+    .await;
+
+    assert_eq!(Stats::load().unwrap().total_bells, 1);
+}
+
+#[tokio::test]
+async fn racing_stops_both_get_an_answer() {
+    isolate();
+    let _serial = SERIAL.lock().await;
+
+    let (first, second) = with_daemon(async {
+        tokio::join!(
+            IpcClient::send_command(Command::Stop),
+            IpcClient::send_command(Command::Stop)
+        )
+    })
+    .await;
+
+    let mut responses = [first, second].map(|r| r.expect("connection dropped"));
+    responses.sort_by_key(|r| matches!(r, Response::ShuttingDown));
+    assert!(
+        matches!(responses, [Response::Ok, Response::ShuttingDown]),
+        "expected one stop and one refusal, got {:?}",
+        responses
+    );
+}
+
+#[tokio::test]
+async fn concurrent_commands_get_their_own_replies() {
+    isolate();
+    let _serial = SERIAL.lock().await;
+
+    let commands = [
+        Command::Status,
+        Command::GetConfig,
+        Command::GetHistory(5),
+        Command::GetStats,
+        Command::ResetTimer,
+        Command::Ring { volume: Some(101) },
+        Command::Status,
+        Command::GetConfig,
+    ];
+    let responses = with_daemon(async {
+        let responses = join_all(commands.iter().cloned().map(IpcClient::send_command)).await;
+        let stop = IpcClient::send_command(Command::Stop).await;
+        assert!(matches!(stop, Ok(Response::Ok)), "{:?}", stop);
+        responses
+    })
+    .await;
+
+    for (command, response) in commands.iter().zip(responses) {
+        let response = response.expect("connection dropped");
+        let matched = match command {
+            Command::Status => matches!(response, Response::Status(_)),
+            Command::GetConfig => matches!(response, Response::Config(_)),
+            Command::GetHistory(_) => matches!(response, Response::History(_)),
+            Command::GetStats => matches!(response, Response::Stats(_)),
+            Command::ResetTimer => matches!(response, Response::Ok),
+            Command::Ring { .. } => {
+                matches!(&response, Response::Error(e) if e.contains("Volume must be"))
+            }
+            _ => unreachable!(),
+        };
+        assert!(matched, "{:?} got {:?}", command, response);
+    }
+}